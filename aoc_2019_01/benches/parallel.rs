@@ -0,0 +1,38 @@
+//!
+//! Compares the sequential and parallel paths on a generated input:
+//!
+//!     cargo bench --features parallel
+//!
+
+use std::io::Cursor;
+use std::time::Instant;
+
+use aoc_2019_01::parallel;
+
+const MODULES: usize = 10_000_000;
+
+fn generate() -> String {
+    let mut seed: u64 = 1;
+    let mut input = String::with_capacity(MODULES * 8);
+    for _ in 0..MODULES {
+        seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        input += &format!("{}\n", (seed >> 33) % 1_000_000);
+    }
+    input
+}
+
+fn main() {
+    let input = generate();
+
+    let start = Instant::now();
+    let modules = aoc_2019_01::parse_modules(Cursor::new(&input)).unwrap();
+    let sequential = (aoc_2019_01::part1(&modules).unwrap(), aoc_2019_01::part2(&modules).unwrap());
+    println!("sequential: {:?}", start.elapsed());
+
+    let start = Instant::now();
+    let modules = parallel::parse_modules(&input).unwrap();
+    let parallel = (parallel::part1(&modules).unwrap(), parallel::part2(&modules).unwrap());
+    println!("parallel:   {:?}", start.elapsed());
+
+    assert_eq!(sequential, parallel);
+}
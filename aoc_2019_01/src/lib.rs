@@ -0,0 +1,323 @@
+//!
+//! Fuel calculations for the day 1 rocket equation, shared by the CLI in
+//! main.rs and any other tool that needs them.
+//!
+
+use std::borrow::Borrow;
+use std::io::BufRead;
+
+#[cfg(feature = "parallel")]
+pub mod parallel;
+
+pub type Result<T> = ::std::result::Result<T, Box<dyn ::std::error::Error>>;
+
+///
+/// Reads one module mass per line. Blank lines are skipped, anything else
+/// that isn't a non-negative number is an error rather than being silently
+/// dropped.
+///
+pub fn parse_modules<R: BufRead>(reader: R) -> Result<Vec<i64>> {
+    let mut modules = Vec::new();
+
+    for (index, line) in reader.lines().enumerate() {
+        if let Some(mass) = parse_mass(index, &line?)? {
+            modules.push(mass);
+        }
+    }
+
+    Ok(modules)
+}
+
+//
+// Parses the line at `index`, `None` for a blank line. The error is a
+// String so that it can cross threads in the parallel path.
+//
+fn parse_mass(index: usize, line: &str) -> ::std::result::Result<Option<i64>, String> {
+    let line = line.trim();
+    if line.is_empty() {
+        return Ok(None);
+    }
+
+    let mass: i64 = line.parse()
+        .map_err(|e| format!("line {}: invalid module mass {:?}: {}", index + 1, line, e))?;
+    if mass < 0 {
+        return Err(format!("line {}: module mass cannot be negative: {}", index + 1, mass));
+    }
+    Ok(Some(mass))
+}
+
+///
+/// Fuel needed by one module: `fuel` for its mass alone (part 1) and
+/// `total_fuel` including the fuel for that fuel (part 2).
+///
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModuleFuel {
+    pub mass: i64,
+    pub fuel: i64,
+    pub total_fuel: i64
+}
+
+impl ModuleFuel {
+    pub fn new(mass: i64) -> ModuleFuel {
+        ModuleFuel {
+            mass,
+            fuel: calculate_fuel(mass),
+            total_fuel: calculate_fuel_recur(mass)
+        }
+    }
+}
+
+pub fn breakdown(modules: &[i64]) -> Vec<ModuleFuel> {
+    modules.iter().map(|&mass| ModuleFuel::new(mass)).collect()
+}
+
+///
+/// Sums both fuel columns of a breakdown, giving the part 1 and part 2
+/// answers.
+///
+pub fn totals(breakdown: &[ModuleFuel]) -> Result<(i64, i64)> {
+    breakdown.iter().try_fold((0i64, 0i64), add_module)
+}
+
+fn add_module((fuel, total_fuel): (i64, i64), module: &ModuleFuel) -> Result<(i64, i64)> {
+    match (fuel.checked_add(module.fuel), total_fuel.checked_add(module.total_fuel)) {
+        (Some(fuel), Some(total_fuel)) => Ok((fuel, total_fuel)),
+        _ => Err("Total fuel overflows".into())
+    }
+}
+
+///
+/// Like `totals`, but folds over the masses as they are read, so the
+/// modules are never collected. Lines are parsed as in `parse_modules`.
+///
+pub fn totals_reader<R: BufRead>(reader: R) -> Result<(i64, i64)> {
+    let mut sums = (0i64, 0i64);
+
+    for (index, line) in reader.lines().enumerate() {
+        if let Some(mass) = parse_mass(index, &line?)? {
+            sums = add_module(sums, &ModuleFuel::new(mass))?;
+        }
+    }
+
+    Ok(sums)
+}
+
+pub fn part1_reader<R: BufRead>(reader: R) -> Result<i64> {
+    Ok(totals_reader(reader)?.0)
+}
+
+pub fn part2_reader<R: BufRead>(reader: R) -> Result<i64> {
+    Ok(totals_reader(reader)?.1)
+}
+
+pub fn part1(modules: &[i64]) -> Result<i64> {
+    Ok(totals(&breakdown(modules))?.0)
+}
+
+pub fn part2(modules: &[i64]) -> Result<i64> {
+    Ok(totals(&breakdown(modules))?.1)
+}
+
+///
+/// Fuel required to launch a module of the given mass, never negative.
+///
+/// ```
+/// use aoc_2019_01::calculate_fuel;
+///
+/// assert_eq!(calculate_fuel(12), 2);
+/// assert_eq!(calculate_fuel(1969), 654);
+/// assert_eq!(calculate_fuel(2), 0);
+/// ```
+///
+pub fn calculate_fuel(weight: i64) -> i64 {
+    let weight = weight / 3 - 2;
+    if weight < 0 {
+        0
+    } else {
+        weight
+    }
+}
+
+///
+/// Fuel for a module including the fuel needed to carry that fuel.
+///
+/// ```
+/// use aoc_2019_01::calculate_fuel_recur;
+///
+/// assert_eq!(calculate_fuel_recur(1969), 966);
+/// ```
+///
+pub fn calculate_fuel_recur(weight: i64) -> i64 {
+    fuel_chain(weight).sum()
+}
+
+///
+/// The terms `calculate_fuel_recur` adds up, for checking the recursion
+/// one step at a time.
+///
+/// ```
+/// use aoc_2019_01::fuel_chain;
+///
+/// assert_eq!(fuel_chain(14).collect::<Vec<_>>(), [2]);
+/// ```
+///
+pub fn fuel_chain(mass: i64) -> impl Iterator<Item = i64> {
+    FuelIter::new(mass)
+}
+
+///
+/// Yields the successive fuel terms for a mass: the fuel for the mass,
+/// then the fuel for that fuel, and so on while it is positive.
+///
+/// ```
+/// use aoc_2019_01::FuelIter;
+///
+/// assert_eq!(FuelIter::new(1969).collect::<Vec<_>>(), [654, 216, 70, 21, 5]);
+/// assert_eq!(FuelIter::new(100756).sum::<i64>(), 50346);
+///
+/// let masses = vec![14, 1969];
+/// assert_eq!(masses.iter().flat_map(FuelIter::new).sum::<i64>(), 2 + 966);
+/// ```
+///
+#[derive(Debug, Clone)]
+pub struct FuelIter {
+    mass: i64
+}
+
+impl FuelIter {
+    pub fn new<M: Borrow<i64>>(mass: M) -> FuelIter {
+        FuelIter { mass: *mass.borrow() }
+    }
+}
+
+impl Iterator for FuelIter {
+    type Item = i64;
+    fn next(&mut self) -> Option<i64> {
+        let fuel = calculate_fuel(self.mass);
+        if fuel <= 0 {
+            None
+        } else {
+            self.mass = fuel;
+            Some(fuel)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    use std::io::Cursor;
+
+    #[test]
+    fn parse_modules_test() {
+        let modules = parse_modules(Cursor::new("12\n14\n1969\n100756")).unwrap();
+        assert_eq!(modules, [12, 14, 1969, 100756]);
+    }
+
+    #[test]
+    fn parse_modules_bad_line_test() {
+        let error = parse_modules(Cursor::new("12\n14a\n1969\n")).unwrap_err();
+        let message = error.to_string();
+        assert!(message.starts_with("line 2: invalid module mass \"14a\""), "{}", message);
+    }
+
+    #[test]
+    fn parse_modules_blank_lines_test() {
+        let modules = parse_modules(Cursor::new("12\n\n14\n")).unwrap();
+        assert_eq!(modules, [12, 14]);
+
+        let modules = parse_modules(Cursor::new("12\r\n14\r\n\n")).unwrap();
+        assert_eq!(modules, [12, 14]);
+    }
+
+    #[test]
+    fn parse_modules_negative_test() {
+        let error = parse_modules(Cursor::new("12\n-14\n")).unwrap_err();
+        assert_eq!(error.to_string(), "line 2: module mass cannot be negative: -14");
+    }
+
+    #[test]
+    fn reader_test() {
+        let input = "12\n14\n\n1969\r\n100756\n";
+        assert_eq!(part1_reader(Cursor::new(input)).unwrap(), 2 + 2 + 654 + 33583);
+        assert_eq!(part2_reader(Cursor::new(input)).unwrap(), 2 + 2 + 966 + 50346);
+
+        let modules = parse_modules(Cursor::new(input)).unwrap();
+        assert_eq!(totals_reader(Cursor::new(input)).unwrap(), totals(&breakdown(&modules)).unwrap());
+
+        let error = part1_reader(Cursor::new("12\n14a\n")).unwrap_err();
+        assert!(error.to_string().starts_with("line 2: invalid module mass"), "{}", error);
+        let huge = format!("{}\n", i64::MAX).repeat(4);
+        assert!(part1_reader(Cursor::new(huge)).is_err());
+    }
+
+    #[test]
+    fn large_mass_test() {
+        // each module needs 715827880 fuel; four of them overflowed an i32 sum
+        let modules = vec![i32::MAX as i64; 4];
+        assert_eq!(part1(&modules).unwrap(), 2863311520);
+        assert!(part2(&modules).unwrap() > i32::MAX as i64);
+
+        let modules = vec![i64::MAX; 4];
+        assert!(part1(&modules).is_err());
+        assert!(part2(&modules).is_err());
+    }
+
+    #[test]
+    fn calculate_fuel_test() {
+        assert_eq!(calculate_fuel(12), 2);
+        assert_eq!(calculate_fuel(1), 0);
+        assert_eq!(calculate_fuel(10), 1);
+    }
+
+    #[test]
+    fn calculate_fuel_recur_test() {
+        assert_eq!(calculate_fuel_recur(100756), 50346);
+    }
+
+    #[test]
+    fn fuel_chain_test() {
+        assert_eq!(fuel_chain(100756).collect::<Vec<_>>(), [33583, 11192, 3728, 1240, 411, 135, 43, 12, 2]);
+        assert_eq!(fuel_chain(100756).sum::<i64>(), 50346);
+        assert_eq!(fuel_chain(2).next(), None);
+    }
+
+    #[test]
+    fn breakdown_test() {
+        let modules = breakdown(&[12, 1969, 100756]);
+        assert_eq!(modules, [
+            ModuleFuel { mass: 12, fuel: 2, total_fuel: 2 },
+            ModuleFuel { mass: 1969, fuel: 654, total_fuel: 966 },
+            ModuleFuel { mass: 100756, fuel: 33583, total_fuel: 50346 }
+        ]);
+        assert_eq!(totals(&modules).unwrap(), (2 + 654 + 33583, 2 + 966 + 50346));
+        assert_eq!(totals(&[]).unwrap(), (0, 0));
+    }
+
+    #[test]
+    fn fuel_iter_test() {
+        assert_eq!(FuelIter::new(12).collect::<Vec<_>>(), [2]);
+        assert_eq!(FuelIter::new(5).next(), None);
+        assert_eq!(FuelIter::new(100756).sum::<i64>(), 50346);
+    }
+
+    // the recursion calculate_fuel_recur used before it summed fuel_chain
+    fn recursive_fuel(weight: i64) -> i64 {
+        let need = calculate_fuel(weight);
+        if need <= 0 {
+            0
+        } else {
+            need + recursive_fuel(need)
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn fuel_iter_matches_recursion(mass in 0i64..1_000_000_000_000) {
+            prop_assert_eq!(FuelIter::new(mass).sum::<i64>(), calculate_fuel_recur(mass));
+            prop_assert_eq!(calculate_fuel_recur(mass), recursive_fuel(mass));
+        }
+    }
+}
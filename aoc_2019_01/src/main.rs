@@ -1,64 +1,81 @@
-use std::io::{self, Read};
+use std::env;
+use std::io;
 
-fn main() -> Result<(), Box<dyn ::std::error::Error>> {
-    let mut input = String::new();
-    io::stdin().read_to_string(&mut input)?;
+use aoc_2019_01::{breakdown, parse_modules, totals, totals_reader, ModuleFuel, Result};
 
-    let input: Vec<i32> = input
-        .split("\n")
-        .filter_map(
-            |s| s.trim().parse().ok())
-        .collect();
+enum BreakdownFormat {
+    Table,
+    Csv
+}
 
-    println!("Part1: {}", part1(&input));
-    println!("Part2: {}", part2(&input));
+fn main() -> Result<()> {
+    let mut parallel = false;
+    let mut format = None;
 
-    Ok(())
-}
+    for arg in env::args().skip(1) {
+        match arg.as_str() {
+            "--parallel" => parallel = true,
+            "--breakdown" | "--breakdown=table" => format = Some(BreakdownFormat::Table),
+            "--breakdown=csv" => format = Some(BreakdownFormat::Csv),
+            _ => return Err(format!("Unknown argument: {}", arg).into())
+        }
+    }
 
-fn part1(modules: &Vec<i32>) -> i32 {
-    modules.iter().map(
-        |&s| calculate_fuel(s)
-    ).sum()
-}
+    let (part1_ans, part2_ans) = if parallel {
+        if format.is_some() {
+            return Err("--breakdown is not supported with --parallel".into());
+        }
+        solve_parallel()?
+    } else if format.is_none() {
+        totals_reader(io::stdin().lock())?
+    } else {
+        let stdin = io::stdin();
+        let modules = breakdown(&parse_modules(stdin.lock())?);
+        let answers = totals(&modules)?;
+
+        match format {
+            Some(BreakdownFormat::Table) => print_table(&modules, answers),
+            Some(BreakdownFormat::Csv) => print_csv(&modules, answers),
+            None => {}
+        }
+        answers
+    };
+
+    println!("Part1: {}", part1_ans);
+    println!("Part2: {}", part2_ans);
 
-fn part2(modules: &Vec<i32>) -> i32 {
-    modules.iter().map(
-        |&s| calculate_fuel_recur(s)
-    ).sum()
+    Ok(())
 }
 
-fn calculate_fuel(weight: i32) -> i32 {
-    let weight = weight / 3 - 2;
-    if weight < 0 {
-        0
-    } else {
-        weight
+fn print_table(modules: &[ModuleFuel], (fuel, total_fuel): (i64, i64)) {
+    println!("{:>12} {:>12} {:>12}", "mass", "fuel", "total_fuel");
+    for module in modules {
+        println!("{:>12} {:>12} {:>12}", module.mass, module.fuel, module.total_fuel);
     }
+    println!("{:>12} {:>12} {:>12}", "total", fuel, total_fuel);
 }
 
-fn calculate_fuel_recur(weight: i32) -> i32 {
-    let need = calculate_fuel(weight);
-    if need <= 0 {
-        0
-    } else {
-        need + calculate_fuel_recur(need)
+fn print_csv(modules: &[ModuleFuel], (fuel, total_fuel): (i64, i64)) {
+    println!("mass,fuel,total_fuel");
+    for module in modules {
+        println!("{},{},{}", module.mass, module.fuel, module.total_fuel);
     }
+    println!("total,{},{}", fuel, total_fuel);
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+#[cfg(feature = "parallel")]
+fn solve_parallel() -> Result<(i64, i64)> {
+    use aoc_2019_01::parallel;
+    use std::io::Read;
 
-    #[test]
-    fn calculate_fuel_test() {
-        assert_eq!(calculate_fuel(12), 2);
-        assert_eq!(calculate_fuel(1), 0);
-        assert_eq!(calculate_fuel(10), 1);
-    }
+    let mut input = String::new();
+    io::stdin().read_to_string(&mut input)?;
 
-    #[test]
-    fn calculate_fuel_recur_test() {
-        assert_eq!(calculate_fuel_recur(100756), 50346);
-    }
+    let input = parallel::parse_modules(&input)?;
+    Ok((parallel::part1(&input)?, parallel::part2(&input)?))
+}
+
+#[cfg(not(feature = "parallel"))]
+fn solve_parallel() -> Result<(i64, i64)> {
+    Err("--parallel needs the `parallel` feature: cargo run --features parallel -- --parallel".into())
 }
@@ -0,0 +1,133 @@
+//!
+//! Rayon-backed versions of the parsing and fuel sums, for inputs with
+//! many millions of modules. Results, including which error is reported
+//! for a bad input, are the same as the sequential functions.
+//!
+
+use rayon::prelude::*;
+
+use crate::{calculate_fuel, calculate_fuel_recur, parse_mass, Result};
+
+// Lines per chunk handed to a worker.
+const CHUNK_LINES: usize = 64 * 1024;
+
+///
+/// Parallel `parse_modules` over input that is already in memory. The
+/// input is cut into chunks of whole lines which are parsed concurrently.
+///
+pub fn parse_modules(input: &str) -> Result<Vec<i64>> {
+    let chunks = split_lines(input, CHUNK_LINES);
+    let parsed: Vec<_> = chunks.par_iter()
+        .map(|&(first_line, chunk)| parse_chunk(first_line, chunk))
+        .collect();
+
+    // report the first bad line, as the sequential parser would
+    let mut modules = Vec::new();
+    for chunk in parsed {
+        modules.extend(chunk?);
+    }
+
+    Ok(modules)
+}
+
+//
+// Splits into pieces of `lines` lines each, paired with the index of
+// their first line.
+//
+fn split_lines(input: &str, lines: usize) -> Vec<(usize, &str)> {
+    let mut chunks = Vec::new();
+    let mut start = 0;
+    let mut first_line = 0;
+
+    while start < input.len() {
+        let end = input[start..].match_indices('\n')
+            .nth(lines - 1)
+            .map_or(input.len(), |(offset, _)| start + offset + 1);
+        chunks.push((first_line, &input[start..end]));
+        start = end;
+        first_line += lines;
+    }
+
+    chunks
+}
+
+fn parse_chunk(first_line: usize, chunk: &str) -> ::std::result::Result<Vec<i64>, String> {
+    let mut modules = Vec::new();
+    for (index, line) in chunk.lines().enumerate() {
+        if let Some(mass) = parse_mass(first_line + index, line)? {
+            modules.push(mass);
+        }
+    }
+    Ok(modules)
+}
+
+fn total_fuel(modules: &[i64], fuel: fn(i64) -> i64) -> Result<i64> {
+    modules.par_iter()
+        .map(|&s| Some(fuel(s)))
+        .try_reduce(|| 0, |a, b| a.checked_add(b))
+        .ok_or_else(|| "Total fuel overflows".into())
+}
+
+pub fn part1(modules: &[i64]) -> Result<i64> {
+    total_fuel(modules, calculate_fuel)
+}
+
+pub fn part2(modules: &[i64]) -> Result<i64> {
+    total_fuel(modules, calculate_fuel_recur)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    // 1M masses from a fixed-seed LCG, with some blank lines mixed in
+    fn fixture() -> String {
+        let mut seed: u64 = 2019;
+        let mut input = String::new();
+        for _ in 0..1_000_000 {
+            seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            let mass = (seed >> 33) % 1_000_000;
+            if mass.is_multiple_of(97) {
+                input.push('\n');
+            }
+            input += &format!("{}\n", mass);
+        }
+        input
+    }
+
+    #[test]
+    fn parallel_matches_sequential_test() {
+        let input = fixture();
+
+        let sequential = crate::parse_modules(Cursor::new(&input)).unwrap();
+        let parallel = parse_modules(&input).unwrap();
+        assert_eq!(parallel, sequential);
+
+        assert_eq!(part1(&parallel).unwrap(), crate::part1(&sequential).unwrap());
+        assert_eq!(part2(&parallel).unwrap(), crate::part2(&sequential).unwrap());
+    }
+
+    #[test]
+    fn split_lines_test() {
+        assert_eq!(split_lines("1\n2\n3\n4\n5", 2), [(0, "1\n2\n"), (2, "3\n4\n"), (4, "5")]);
+        assert_eq!(split_lines("1\n2\n", 2), [(0, "1\n2\n")]);
+        assert!(split_lines("", 2).is_empty());
+    }
+
+    #[test]
+    fn parallel_errors_test() {
+        let input = "12\n14a\n-3\nxyz\n";
+        let sequential = crate::parse_modules(Cursor::new(input)).unwrap_err();
+        let parallel = parse_modules(input).unwrap_err();
+        assert_eq!(parallel.to_string(), sequential.to_string());
+
+        let mut input = fixture();
+        input += "oops\n";
+        let sequential = crate::parse_modules(Cursor::new(&input)).unwrap_err();
+        let parallel = parse_modules(&input).unwrap_err();
+        assert_eq!(parallel.to_string(), sequential.to_string());
+
+        assert!(part1(&[i64::MAX; 4]).is_err());
+    }
+}
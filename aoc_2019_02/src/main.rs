@@ -1,125 +1,265 @@
+use std::env;
 use std::io::{self};
+use std::iter::empty;
+use std::iter::{Skip, StepBy};
+use std::ops::RangeInclusive;
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::thread;
 
-enum Instruction {
-    Add { left_op: usize, right_op: usize, into: usize },
-    Mul { left_op: usize, right_op: usize, into: usize },
-    Terminate,
+use intcode::{decode, Instruction, IntCode, IntCodeError};
+
+type Result<T> = ::std::result::Result<T, Box<dyn ::std::error::Error>>;
+
+//
+// Runs a program to completion and returns its final memory.
+//
+#[allow(dead_code)]
+fn run(program: &[i64]) -> Result<Vec<i64>> {
+    let mut machine = IntCode::init(program, empty());
+    machine.run_to_termination()?;
+    Ok(machine.memory().to_vec())
 }
 
-struct Memory {
-    memory: Vec<u32>,
-    address_ptr: usize,
+//
+// Parameters of the part 2 search, overridable from the command line with
+// `--target N`, `--noun-range A..=B` and `--verb-range A..=B`. `--all`
+// lists every matching pair instead of just the first.
+//
+struct Search {
+    target: i64,
+    nouns: RangeInclusive<i64>,
+    verbs: RangeInclusive<i64>,
+    all: bool
 }
 
-impl Memory {
-    fn init(memory: &Vec<u32>) -> Memory {
-        Memory {
-            memory: memory.clone(),
-            address_ptr: 0
-        }
-    }
+impl Search {
+    fn from_args<I: Iterator<Item = String>>(mut args: I) -> Result<Search> {
+        let mut search = Search {
+            target: 19690720,
+            nouns: 0..=99,
+            verbs: 0..=99,
+            all: false
+        };
 
-    fn read_instruction(&mut self) -> Result<(Instruction), Box<dyn ::std::error::Error>> {
-        let opCode = self.memory.get(self.address_ptr).ok_or("Invalid Address")?;
-        let instruction = match opCode {
-            1 => {
-                self.address_ptr += 4;
-                Instruction::Add {
-                    left_op: *self.memory.get(self.address_ptr - 3).ok_or("Invalid Address")? as usize,
-                    right_op: *self.memory.get(self.address_ptr - 2).ok_or("Invalid Address")? as usize,
-                    into: *self.memory.get(self.address_ptr - 1).ok_or("Invalid Address")? as usize,
-                }
+        while let Some(arg) = args.next() {
+            if arg == "--all" {
+                search.all = true;
+                continue;
             }
-            2 => {
-                self.address_ptr += 4;
-                Instruction::Mul {
-                    left_op: *self.memory.get(self.address_ptr - 3).ok_or("Invalid Address")? as usize,
-                    right_op: *self.memory.get(self.address_ptr - 2).ok_or("Invalid Address")? as usize,
-                    into: *self.memory.get(self.address_ptr - 1).ok_or("Invalid Address")? as usize,
-                }
-            }
-            99 => {
-                Instruction::Terminate
-            }
-            _ => {
-                return Err("Invalid Opcode".into());
+
+            let value = args.next().ok_or(format!("Missing value for {}", arg))?;
+            match arg.as_str() {
+                "--target" => search.target = value.parse()?,
+                "--noun-range" => search.nouns = parse_range(&value)?,
+                "--verb-range" => search.verbs = parse_range(&value)?,
+                _ => return Err(format!("Unknown argument: {}", arg).into())
             }
-        };
+        }
 
-        Ok(instruction)
+        Ok(search)
     }
+}
 
-    fn run(&mut self) -> Result<(&Vec<u32>), Box<dyn ::std::error::Error>> {
-        loop {
-            let instruction = self.read_instruction()?;
+fn parse_range(range: &str) -> Result<RangeInclusive<i64>> {
+    let mut bounds = range.splitn(2, "..=");
+    let start = bounds.next().ok_or("Invalid range")?.trim().parse()?;
+    let end = bounds.next().ok_or(format!("Invalid range {:?}, expected start..=end", range))?.trim().parse()?;
+    Ok(start..=end)
+}
 
-            match instruction {
-                Instruction::Add { left_op, right_op, into } => {
-                    let sum = self.memory.get(left_op).ok_or("Invalid address reference")? + self.memory.get(right_op).ok_or("Invalid address reference")?;
-                    let intoRef = self.memory.get_mut(into).ok_or("Invalid address reference")?;
-                    *intoRef = sum;
-                },
-                Instruction::Mul { left_op, right_op, into } => {
-                    let mul = self.memory.get(left_op).ok_or("Invalid address reference")? * self.memory.get(right_op).ok_or("Invalid address reference")?;
-                    let intoRef = self.memory.get_mut(into).ok_or("Invalid address reference")?;
-                    *intoRef = mul;
-                }
-                Instruction::Terminate => {
-                    return Ok(&self.memory);
-                }
-            };
-        }
-    }
+fn parse_input(input: &str) -> Vec<i64> {
+    input
+        .split(",")
+        .filter_map(|s|
+                    s.trim().parse().ok()
+        ).collect()
 }
 
-fn main() -> Result<(), Box<dyn ::std::error::Error>> {
+fn main() -> Result<()> {
+    let search = Search::from_args(env::args().skip(1))?;
+
     let mut input = String::new();
     io::stdin().read_line(&mut input)?;
 
-    let input: Vec<u32> = input
-        .split(",")
-        .filter_map(|s|
-                    s.trim().parse().ok()
-        ).collect();
+    let input = parse_input(&input);
 
     println!("Part1: {}", part1(&input)?);
-    println!("Part2: {:?}", part2(&input)?);
+    if search.all {
+        println!("Part2: {:?}", find_all_pairs(&input, search.target, search.nouns, search.verbs)?);
+    } else {
+        println!("Part2: {:?}", part2_parallel(&input, search.target, search.nouns, search.verbs)?);
+    }
 
     Ok(())
 }
 
-fn part1(input: &Vec<u32>) -> Result<(u32), Box<dyn ::std::error::Error>> {
-    let mut mutInput = input.clone();
+//
+// Both parts patch the noun and verb into addresses 1 and 2, which an
+// empty or truncated input doesn't have.
+//
+fn check_patchable(program: &[i64]) -> Result<()> {
+    if program.len() < 3 {
+        return Err(format!("program too short to patch noun/verb: {} cells", program.len()).into());
+    }
+    Ok(())
+}
+
+//
+// Decodes the program from the start up to its first halt. A search
+// skips pairs that make the program misbehave, which would turn a broken
+// program (say, an invalid opcode the patched noun and verb can't reach)
+// into an unhelpful "Fail to find pair". Catching that up front means any
+// error left during the search comes from the noun and verb.
+//
+fn check_searchable(program: &[i64]) -> Result<()> {
+    check_patchable(program)?;
 
-    mutInput[1] = 12;
-    mutInput[2] = 2;
+    let mut address = 0;
+    loop {
+        let (instruction, next) = decode(program, address)?;
+        if instruction == Instruction::Terminate {
+            return Ok(());
+        }
+        address = next;
+    }
+}
 
-    let mut mem = Memory::init(&mutInput);
-    let output = mem.run()?;
+fn part1(input: &[i64]) -> Result<i64> {
+    check_patchable(input)?;
+    let mut machine = IntCode::init_with_patches(input, &[(1, 12), (2, 2)], empty())?;
+    machine.run_to_termination()?;
 
-    Ok(output[0])
+    machine.memory().first().copied().ok_or("program has no address 0 to read the result from".into())
 }
 
-fn part2(input: &Vec<u32>) -> Result<(u32, u32), Box<dyn ::std::error::Error>> {
-    for noun in 0..99 {
-        for verb in 0..99 {
-            let mut testInput = input.clone();
-            testInput[1] = noun;
-            testInput[2] = verb;
-            let mut mem = Memory::init(&testInput);
-            match mem.run() {
-                Ok(output) => {
-                    if output[0] == 19690720 {
-                        return Ok((noun, verb));
+//
+// Runs the program with the given noun and verb and checks whether it leaves
+// the target in address 0. The machine, built on the base program, is reset
+// in place rather than built from a fresh copy of it for every attempt.
+//
+// A bad noun or verb can send the program off into garbage, which just
+// means the pair doesn't match. Anything else (the program asking for
+// input, say) means it isn't a day 2 program at all and is passed on.
+//
+fn try_pair(machine: &mut IntCode<std::iter::Empty<i64>>, noun: i64, verb: i64, target: i64) -> Result<bool> {
+    machine.reset();
+    machine.memory_mut()[1] = noun;
+    machine.memory_mut()[2] = verb;
+
+    match machine.run_to_termination() {
+        Ok(()) => Ok(machine.memory().first() == Some(&target)),
+        Err(error) => match IntCodeError::find(error.as_ref()) {
+            Some(IntCodeError::OutOfInput) | None => Err(error),
+            Some(_) => Ok(false)
+        }
+    }
+}
+
+#[allow(dead_code)]
+fn part2(input: &[i64], target: i64, nouns: RangeInclusive<i64>, verbs: RangeInclusive<i64>) -> Result<(i64, i64)> {
+    check_searchable(input)?;
+    let mut machine = IntCode::init(input, empty());
+    for noun in nouns {
+        for verb in verbs.clone() {
+            if try_pair(&mut machine, noun, verb, target)? {
+                return Ok((noun, verb));
+            }
+        }
+    }
+    Err("Fail to find pair".into())
+}
+
+type NounShare = StepBy<Skip<RangeInclusive<i64>>>;
+
+//
+// Deals the nouns out round-robin to one thread per core and collects what
+// each thread returns, in thread order. Boxed errors can't cross threads,
+// so workers report failures as strings.
+//
+fn on_workers<R, F>(nouns: &RangeInclusive<i64>, work: F) -> Vec<R> where
+    R: Send,
+    F: Fn(NounShare) -> R + Sync
+{
+    let workers = thread::available_parallelism().map_or(1, |n| n.get());
+
+    thread::scope(|scope| {
+        let handles: Vec<_> = (0..workers).map(|worker| {
+            let nouns = nouns.clone().skip(worker).step_by(workers);
+            let work = &work;
+            scope.spawn(move || work(nouns))
+        }).collect();
+
+        handles.into_iter()
+            .map(|handle| handle.join().unwrap())
+            .collect()
+    })
+}
+
+//
+// Same search as part2, spread over on_workers, returning the matching
+// pair with the smallest 100 * noun + verb, and of pairs that tie on that
+// the smaller noun. A thread skips any pair scoring more than the best
+// match any thread has found so far, so the answer doesn't depend on how
+// the threads are scheduled. With verbs past 99 this can differ from
+// part2, which takes the smallest noun first.
+//
+fn part2_parallel(input: &[i64], target: i64, nouns: RangeInclusive<i64>, verbs: RangeInclusive<i64>) -> Result<(i64, i64)> {
+    check_searchable(input)?;
+    let key = |(noun, verb): (i64, i64)| (100 * noun + verb, noun);
+    let best_score = AtomicI64::new(i64::MAX);
+
+    let found = on_workers(&nouns, |nouns| {
+        let mut machine = IntCode::init(input, empty());
+        let mut best = None;
+        for noun in nouns {
+            // nouns only grow, so no later one can score any better
+            if 100 * noun + verbs.start() > best_score.load(Ordering::Relaxed) {
+                break;
+            }
+            for verb in verbs.clone() {
+                let score = 100 * noun + verb;
+                if score > best_score.load(Ordering::Relaxed) {
+                    break;
+                }
+                if try_pair(&mut machine, noun, verb, target).map_err(|e| e.to_string())? {
+                    best_score.fetch_min(score, Ordering::Relaxed);
+                    if best.is_none_or(|best| key((noun, verb)) < key(best)) {
+                        best = Some((noun, verb));
                     }
+                    break;
                 }
-                Err(error) => {
-                    continue;
+            }
+        }
+        Ok(best)
+    });
+
+    let found = found.into_iter().collect::<::std::result::Result<Vec<_>, String>>()?;
+    found.into_iter().flatten().min_by_key(|pair| key(*pair)).ok_or("Fail to find pair".into())
+}
+
+//
+// Every pair that produces the target, ordered by noun and then verb.
+//
+fn find_all_pairs(input: &[i64], target: i64, nouns: RangeInclusive<i64>, verbs: RangeInclusive<i64>) -> Result<Vec<(i64, i64)>> {
+    check_searchable(input)?;
+
+    let found = on_workers(&nouns, |nouns| {
+        let mut machine = IntCode::init(input, empty());
+        let mut pairs = Vec::new();
+        for noun in nouns {
+            for verb in verbs.clone() {
+                if try_pair(&mut machine, noun, verb, target).map_err(|e| e.to_string())? {
+                    pairs.push((noun, verb));
                 }
             }
         }
-    }
-    Err("Fail to find pair".into())
+        Ok(pairs)
+    });
+
+    let found = found.into_iter().collect::<::std::result::Result<Vec<_>, String>>()?;
+    let mut pairs: Vec<_> = found.into_iter().flatten().collect();
+    pairs.sort_unstable();
+    Ok(pairs)
 }
 
 #[cfg(test)]
@@ -128,20 +268,172 @@ mod test {
 
     #[test]
     fn test_memory() {
-        let mut mem = Memory::init(&vec![1,9,10,3,2,3,11,0,99,30,40,50]);
-        assert_eq!(*mem.run().unwrap(), vec![3500,9,10,70,2,3,11,0,99,30,40,50]);
+        assert_eq!(run(&[1,9,10,3,2,3,11,0,99,30,40,50]).unwrap(), vec![3500,9,10,70,2,3,11,0,99,30,40,50]);
+        assert_eq!(run(&[1,0,0,0,99]).unwrap(), vec![2,0,0,0,99]);
+        assert_eq!(run(&[2,3,0,3,99]).unwrap(), vec![2,3,0,6,99]);
+        assert_eq!(run(&[2,4,4,5,99,0]).unwrap(), vec![2,4,4,5,99,9801]);
+        assert_eq!(run(&[1,1,1,4,99,5,6,0,99]).unwrap(), vec![30,1,1,4,2,5,6,0,99]);
+    }
+
+    #[test]
+    fn test_program_too_short() {
+        let expected = "program too short to patch noun/verb: 0 cells";
+        assert_eq!(part1(&parse_input("")).unwrap_err().to_string(), expected);
+        assert_eq!(part2(&parse_input(""), 0, 0..=99, 0..=99).unwrap_err().to_string(), expected);
+
+        let expected = "program too short to patch noun/verb: 2 cells";
+        assert_eq!(part1(&parse_input("1,0")).unwrap_err().to_string(), expected);
+        assert_eq!(part2_parallel(&parse_input("1,0"), 0, 0..=99, 0..=99).unwrap_err().to_string(), expected);
+    }
+
+    #[test]
+    fn test_part2_boundary() {
+        // 0: ADD #noun, #verb -> [0]
+        let program = vec![1101,0,0,0,99];
+        assert_eq!(part2(&program, 198, 0..=99, 0..=99).unwrap(), (99, 99));
+        assert_eq!(part2(&program, 5, 0..=99, 0..=99).unwrap(), (0, 5));
+        assert_eq!(part2(&program, 5, 3..=99, 0..=99).unwrap(), (3, 2));
+    }
+
+    #[test]
+    fn test_part2_missing_target() {
+        let program = vec![1101,0,0,0,99];
+        assert!(part2(&program, 1000, 0..=99, 0..=99).is_err());
+        assert!(part2(&program, 198, 0..=98, 0..=99).is_err());
+    }
+
+    #[test]
+    fn test_part2_parallel() {
+        // 0: ADD #noun, #verb -> [0], so every pair summing to the target
+        // matches and the smallest noun has to win
+        let program = vec![1101,0,0,0,99];
+        assert_eq!(part2_parallel(&program, 5, 0..=99, 0..=99).unwrap(), (0, 5));
+        assert_eq!(part2_parallel(&program, 150, 0..=99, 0..=99).unwrap(), (51, 99));
+        assert_eq!(part2_parallel(&program, 150, 60..=99, 0..=99).unwrap(), (60, 90));
+        assert!(part2_parallel(&program, 1000, 0..=99, 0..=99).is_err());
+
+        for target in 0..=198 {
+            assert_eq!(part2_parallel(&program, target, 0..=99, 0..=99).unwrap(),
+                       part2(&program, target, 0..=99, 0..=99).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_part2_two_pairs() {
+        // 0: MUL #noun, #verb -> [0]; target 12 is matched by (2, 6), (3, 4),
+        // (4, 3) and (6, 2) within 2..=6, and (2, 6) has the smallest
+        // 100 * noun + verb
+        let program = vec![1102,0,0,0,99];
+        assert_eq!(part2(&program, 12, 2..=6, 2..=6).unwrap(), (2, 6));
+        assert_eq!(part2_parallel(&program, 12, 2..=6, 2..=6).unwrap(), (2, 6));
+
+        // 0: ADD #noun, #verb -> [20]
+        // 4: MUL [1], #149 -> [21]
+        // 8: ADD [20], [21] -> [0]
+        // so address 0 ends up holding 150 * noun + verb, and target 150 is
+        // matched by (0, 150), the smallest noun, and by (1, 0), the
+        // smallest 100 * noun + verb
+        let mut program = vec![1101,0,0,20,1002,1,149,21,1,20,21,0,99];
+        program.resize(22, 0);
+        assert_eq!(find_all_pairs(&program, 150, 0..=99, 0..=199).unwrap(), [(0, 150), (1, 0)]);
+        assert_eq!(part2_parallel(&program, 150, 0..=99, 0..=199).unwrap(), (1, 0));
+        assert_eq!(part2_parallel(&program, 150, 0..=1, 0..=150).unwrap(), (1, 0));
 
-        let mut mem = Memory::init(&vec![1,0,0,0,99]);
-        assert_eq!(*mem.run().unwrap(), vec![2,0,0,0,99]);
+        // the same with 100 * noun + verb in address 0: (0, 100) and (1, 0)
+        // both score 100, and the smaller noun wins
+        program[6] = 99;
+        assert_eq!(find_all_pairs(&program, 100, 0..=99, 0..=199).unwrap(), [(0, 100), (1, 0)]);
+        assert_eq!(part2_parallel(&program, 100, 0..=99, 0..=199).unwrap(), (0, 100));
+    }
+
+    //
+    // Rough comparison of the sequential and parallel searches on a
+    // program that never matches, so both have to try every pair. Run with
+    // `cargo test --release -- --ignored --nocapture`.
+    //
+    #[test]
+    #[ignore]
+    fn bench_part2() {
+        use std::time::Instant;
 
-        let mut mem = Memory::init(&vec![2,3,0,3,99]);
-        assert_eq!(*mem.run().unwrap(), vec![2,3,0,6,99]);
+        let mut program = vec![1,0,0,3,1,1,2,3,1,3,4,3,1,5,0,3,2,1,10,19,1,6,19,23,99];
+        program.resize(200, 0);
 
-        let mut mem = Memory::init(&vec![2,4,4,5,99,0]);
-        assert_eq!(*mem.run().unwrap(), vec![2,4,4,5,99,9801]);
+        for _ in 0..3 {
+            let start = Instant::now();
+            assert!(part2(&program, -1, 0..=99, 0..=99).is_err());
+            let sequential = start.elapsed();
+
+            let start = Instant::now();
+            assert!(part2_parallel(&program, -1, 0..=99, 0..=99).is_err());
+            let parallel = start.elapsed();
+
+            println!("sequential: {:?}, parallel: {:?}", sequential, parallel);
+        }
+    }
+
+    #[test]
+    fn test_find_all_pairs() {
+        // 0: ADD #noun, #verb -> [9]
+        // 4: ADD [1], #0 -> [0]
+        // so address 0 ends up holding the noun, whatever the verb
+        let program = vec![1101,0,0,9,1001,1,0,0,99,0];
+        let expected: Vec<_> = (0..=99).map(|verb| (42, verb)).collect();
+        assert_eq!(find_all_pairs(&program, 42, 0..=99, 0..=99).unwrap(), expected);
+        assert_eq!(part2_parallel(&program, 42, 0..=99, 0..=99).unwrap(), (42, 0));
+        assert!(find_all_pairs(&program, 100, 0..=99, 0..=99).unwrap().is_empty());
 
-        let mut mem = Memory::init(&vec![1,1,1,4,99,5,6,0,99]);
-        assert_eq!(*mem.run().unwrap(), vec![30,1,1,4,2,5,6,0,99]);
+        let program = vec![1102,0,0,0,99];
+        assert_eq!(find_all_pairs(&program, 12, 2..=6, 2..=6).unwrap(), [(2, 6), (3, 4), (4, 3), (6, 2)]);
     }
 
+    #[test]
+    fn test_unrecoverable_errors() {
+        // 0: JT #noun, #verb; 3: IN -> [0]
+        // nonzero nouns jump into the invalid opcodes from 6 on, which only
+        // rules the pair out, but noun 0 asks for input a day 2 program
+        // never gets
+        let program = vec![1105,0,0,3,0,99,42,42,42,42];
+        assert_eq!(part2(&program, 0, 1..=5, 6..=9).unwrap_err().to_string(), "Fail to find pair");
+        assert_eq!(part2(&program, 0, 0..=5, 6..=9).unwrap_err().to_string(), "IN -> [0]: Ran out of input");
+        assert_eq!(part2_parallel(&program, 0, 0..=5, 6..=9).unwrap_err().to_string(), "IN -> [0]: Ran out of input");
+        assert_eq!(find_all_pairs(&program, 0, 0..=5, 6..=9).unwrap_err().to_string(), "IN -> [0]: Ran out of input");
+    }
+
+    #[test]
+    fn test_broken_base_program() {
+        // 0: ADD [noun], [verb] -> [0]; 4: invalid opcode 42
+        let program = vec![1,0,0,0,42,99];
+        for error in [
+            part2(&program, 0, 0..=99, 0..=99).unwrap_err(),
+            part2_parallel(&program, 0, 0..=99, 0..=99).unwrap_err(),
+            find_all_pairs(&program, 0, 0..=99, 0..=99).unwrap_err()
+        ] {
+            assert_eq!(IntCodeError::find(error.as_ref()), Some(&IntCodeError::InvalidOpcode(42)));
+        }
+
+        // 0: ADD [noun], [verb] -> [4]; 4: HALT, unless a pair overwrites
+        // it with an invalid opcode (198 or 0), which only rules that pair
+        // out
+        let program = vec![1,0,0,4,99,0];
+        assert_eq!(find_all_pairs(&program, 1, 4..=5, 4..=5).unwrap(), [(4, 5), (5, 4)]);
+    }
+
+    #[test]
+    fn test_search_args() {
+        let args = ["--target", "42", "--noun-range", "1..=5"].iter().map(|s| s.to_string());
+        let search = Search::from_args(args).unwrap();
+        assert_eq!(search.target, 42);
+        assert_eq!(search.nouns, 1..=5);
+        assert_eq!(search.verbs, 0..=99);
+        assert!(!search.all);
+
+        let args = ["--all", "--target", "7"].iter().map(|s| s.to_string());
+        let search = Search::from_args(args).unwrap();
+        assert!(search.all);
+        assert_eq!(search.target, 7);
+
+        let args = ["--verb-range", "1..5"].iter().map(|s| s.to_string());
+        assert!(Search::from_args(args).is_err());
+    }
 }
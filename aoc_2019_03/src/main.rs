@@ -1,6 +1,6 @@
-use std::collections::HashSet;
 use std::collections::HashMap;
-use std::iter::FromIterator;
+
+use std::io::Read;
 
 type Result<T> = ::std::result::Result<T, Box<dyn ::std::error::Error>>;
 
@@ -25,138 +25,630 @@ struct Segment {
 }
 
 fn main() -> Result<()> {
-    let mut line0 = String::new();
-    let mut line1 = String::new();
+    let mut input = String::new();
+    std::io::stdin().read_to_string(&mut input)?;
 
-    std::io::stdin().read_line(&mut line0)?;
-    std::io::stdin().read_line(&mut line1)?;
+    let wires = parse_wires(&input)?;
 
-    let path0 = parse_input(&line0)?;
-    let path1 = parse_input(&line1)?;
+    let args: Vec<String> = std::env::args().skip(1).collect();
 
-    println!("{}", part1(&path0, &path1)?);
-    println!("{}", part2(&path0, &path1)?);
+    if args.iter().any(|arg| arg == "--list") {
+        for i in crossings(&wires) {
+            println!("({}, {}): manhattan {}, steps {}", i.x, i.y, i.manhattan, i.combined_steps);
+        }
+        println!("{} intersections", intersection_count(&wires));
+    }
+
+    if let Some(position) = args.iter().position(|arg| arg == "--svg") {
+        let file = args.get(position + 1).ok_or("--svg needs an output file")?;
+        std::fs::write(file, render_svg(&wires, &crossings(&wires)))?;
+    }
+
+    println!("{}", part1(&wires)?);
+    println!("{}", part2(&wires)?);
     Ok(())
 }
 
-fn path_to_coords(path: &Vec<Segment>) -> Vec<(i32, i32)> {
-    let mut coords = Vec::<(i32, i32)>::new();
-    let mut y: i32 = 0;
-    let mut x: i32 = 0;
+//
+// Axis-aligned stretch of wire between two corners, as (y, x) like
+// first_visits, along with the number of steps the wire has taken before
+// reaching `from`. Coordinates are i64 so that very long wires can be
+// accumulated without overflow.
+//
+struct Line {
+    from: (i64, i64),
+    to: (i64, i64),
+    steps: i64
+}
+
+impl Line {
+    fn steps_to(&self, point: (i64, i64)) -> i64 {
+        self.steps + (point.0 - self.from.0).abs() + (point.1 - self.from.1).abs()
+    }
+
+    fn is_vertical(&self) -> bool {
+        self.from.1 == self.to.1
+    }
+
+    fn y_range(&self) -> (i64, i64) {
+        (i64::min(self.from.0, self.to.0), i64::max(self.from.0, self.to.0))
+    }
+
+    fn x_range(&self) -> (i64, i64) {
+        (i64::min(self.from.1, self.to.1), i64::max(self.from.1, self.to.1))
+    }
+}
+
+fn path_to_lines(path: &Vec<Segment>) -> Vec<Line> {
+    let mut lines = Vec::new();
+    let mut from = (0, 0);
+    let mut steps = 0;
 
     for s in path {
-        for cnt in 0..s.length {
-            y += s.direction.value().0 as i32;
-            x += s.direction.value().1 as i32;
-            coords.push((y, x));
+        let (dy, dx) = s.direction.value();
+        let to = (from.0 + dy as i64 * s.length as i64,
+                  from.1 + dx as i64 * s.length as i64);
+        lines.push(Line { from, to, steps });
+        from = to;
+        steps += s.length as i64;
+    }
+
+    lines
+}
+
+//
+// A cell both wires pass through, as (y, x) like path_to_lines, with its
+// distance from the origin and the combined number of steps the wires
+// take to first reach it.
+//
+#[derive(Debug, PartialEq)]
+struct Intersection {
+    x: i64,
+    y: i64,
+    manhattan: i64,
+    combined_steps: i64
+}
+
+//
+// Every cell where the two wires cross, closest to the origin first.
+// Segments are intersected pairwise instead of visiting every cell, so
+// the cost depends on the number of segments rather than their length;
+// only segments overlapping along the same line contribute one
+// intersection per shared cell. The origin itself is never reported.
+//
+fn intersections(path0: &Vec<Segment>, path1: &Vec<Segment>) -> Vec<Intersection> {
+    let lines0 = path_to_lines(path0);
+    let lines1 = path_to_lines(path1);
+    let mut steps: HashMap<(i64, i64), i64> = HashMap::new();
+
+    for a in &lines0 {
+        for b in &lines1 {
+            let (a_y0, a_y1) = a.y_range();
+            let (a_x0, a_x1) = a.x_range();
+            let (b_y0, b_y1) = b.y_range();
+            let (b_x0, b_x1) = b.x_range();
+
+            if a_y1 < b_y0 || b_y1 < a_y0 || a_x1 < b_x0 || b_x1 < a_x0 {
+                continue;
+            }
+
+            let points: Vec<(i64, i64)> = match (a.is_vertical(), b.is_vertical()) {
+                (true, false) => vec![(b.from.0, a.from.1)],
+                (false, true) => vec![(a.from.0, b.from.1)],
+                (true, true) => {
+                    let x = a.from.1;
+                    (i64::max(a_y0, b_y0)..=i64::min(a_y1, b_y1)).map(|y| (y, x)).collect()
+                },
+                (false, false) => {
+                    let y = a.from.0;
+                    (i64::max(a_x0, b_x0)..=i64::min(a_x1, b_x1)).map(|x| (y, x)).collect()
+                }
+            };
+
+            // a wire can reach the same cell along several segments; the
+            // first visit is the one that counts
+            for point in points {
+                if point != (0, 0) {
+                    let combined = a.steps_to(point) + b.steps_to(point);
+                    let best = steps.entry(point).or_insert(combined);
+                    *best = i64::min(*best, combined);
+                }
+            }
         }
     }
 
-    coords
+    let mut intersections: Vec<Intersection> = steps.into_iter()
+        .map(|((y, x), combined_steps)| Intersection { x, y, manhattan: y.abs() + x.abs(), combined_steps })
+        .collect();
+    intersections.sort_by_key(|i| (i.manhattan, i.combined_steps, i.y, i.x));
+    intersections
 }
-fn part1(path0: &Vec<Segment>, path1: &Vec<Segment>) -> Result<i32>
-{
-    // based off https://github.com/Ummon/AdventOfCode2019/blob/master/src/day03.rs
-    let positions0: HashSet<(i32, i32)> = HashSet::from_iter(path_to_coords(path0));
-    let positions1: HashSet<(i32, i32)> = HashSet::from_iter(path_to_coords(path1));
-    let intersection: HashSet<_> = positions0.intersection(&positions1).collect();
 
-    Ok(intersection.iter().map(|(y, x)| y.abs() + x.abs()).min().unwrap())
+//
+// Every cell where at least two different wires cross, ordered like
+// `intersections`, which each pair of wires goes through. A cell shared
+// by several pairs is reported once, with the fewest combined steps of
+// any pair crossing there; with two wires this is just `intersections`.
+//
+fn crossings(wires: &[Vec<Segment>]) -> Vec<Intersection> {
+    let mut best: HashMap<(i64, i64), Intersection> = HashMap::new();
+
+    for (index, wire0) in wires.iter().enumerate() {
+        for wire1 in &wires[index + 1..] {
+            for crossing in intersections(wire0, wire1) {
+                match best.get(&(crossing.y, crossing.x)) {
+                    Some(known) if known.combined_steps <= crossing.combined_steps => {},
+                    _ => { best.insert((crossing.y, crossing.x), crossing); }
+                }
+            }
+        }
+    }
+
+    let mut crossings: Vec<Intersection> = best.into_values().collect();
+    crossings.sort_by_key(|i| (i.manhattan, i.combined_steps, i.y, i.x));
+    crossings
 }
 
-fn part2(path0: &Vec<Segment>, path1: &Vec<Segment>) -> Result<i32>
-{
-    let positions0 = path_to_coords(path0);
-    let positions1 = path_to_coords(path1);
-    let positions0_map: HashMap<&(i32, i32), usize> = HashMap::from_iter(positions0.iter().enumerate().map(|(i, pos)| (pos, i)));
+//
+// Number of distinct cells at least two wires share, not counting the
+// origin. A cell is counted once however many times or by however many
+// wires it is crossed, and a wire crossing only itself doesn't count.
+//
+fn intersection_count(wires: &[Vec<Segment>]) -> usize {
+    crossings(wires).len()
+}
+
+fn part1(wires: &[Vec<Segment>]) -> Result<i64> {
+    crossings(wires).iter()
+        .map(|i| i.manhattan)
+        .min()
+        .ok_or("Wires never cross".into())
+}
 
-    let best = positions1.iter().enumerate().filter_map(
-        |(index, pos)|
-        if let Some(s) = positions0_map.get(pos) {
-            Some(s + index)
+fn part2(wires: &[Vec<Segment>]) -> Result<i64> {
+    crossings(wires).iter()
+        .map(|i| i.combined_steps)
+        .min()
+        .ok_or("Wires never cross".into())
+}
+
+//
+// Draws the wires as an SVG image: the first wire red, the second blue
+// and any others in further colours, with a circle on every
+// intersection. The closest intersection (part 1) is filled green and
+// the one with the fewest steps (part 2) orange. The view box fits the
+// wires with a small margin; up is negative y, so the image isn't
+// flipped.
+//
+fn render_svg(wires: &[Vec<Segment>], intersections: &[Intersection]) -> String {
+    let wires: Vec<Vec<Line>> = wires.iter().map(path_to_lines).collect();
+    let corners: Vec<(i64, i64)> = std::iter::once((0, 0))
+        .chain(wires.iter().flatten().map(|line| line.to))
+        .collect();
+
+    let min_y = corners.iter().map(|c| c.0).min().unwrap_or(0);
+    let max_y = corners.iter().map(|c| c.0).max().unwrap_or(0);
+    let min_x = corners.iter().map(|c| c.1).min().unwrap_or(0);
+    let max_x = corners.iter().map(|c| c.1).max().unwrap_or(0);
+    let extent = i64::max(1, i64::max(max_y - min_y, max_x - min_x));
+    let margin = extent / 20 + 1;
+    let (width, height) = (max_x - min_x + 2 * margin, max_y - min_y + 2 * margin);
+    let radius = f64::max(0.25, extent as f64 / 100.0);
+
+    let mut svg = format!("<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"{} {} {} {}\" width=\"800\" height=\"{}\">\n",
+                          min_x - margin, min_y - margin, width, height, 800 * height / width);
+
+    for (lines, color) in wires.iter().zip(["red", "blue", "purple", "teal", "brown"].iter().cycle()) {
+        let points: Vec<String> = std::iter::once((0, 0))
+            .chain(lines.iter().map(|line| line.to))
+            .map(|(y, x)| format!("{},{}", x, y))
+            .collect();
+        svg.push_str(&format!("  <polyline points=\"{}\" fill=\"none\" stroke=\"{}\" stroke-width=\"1\" vector-effect=\"non-scaling-stroke\"/>\n",
+                              points.join(" "), color));
+    }
+
+    let closest = intersections.iter().min_by_key(|i| i.manhattan).map(|i| (i.x, i.y));
+    let fewest = intersections.iter().min_by_key(|i| i.combined_steps).map(|i| (i.x, i.y));
+    for i in intersections {
+        let fill = if closest == Some((i.x, i.y)) {
+            "green"
+        } else if fewest == Some((i.x, i.y)) {
+            "orange"
         } else {
-            None
-        }
-    ).min().unwrap();
+            "none"
+        };
+        svg.push_str(&format!("  <circle cx=\"{}\" cy=\"{}\" fill=\"{}\" r=\"{}\" stroke=\"black\" stroke-width=\"1\" vector-effect=\"non-scaling-stroke\"/>\n",
+                              i.x, i.y, fill, radius));
+    }
 
-    Ok((best + 2) as i32)
+    svg.push_str("</svg>\n");
+    svg
 }
 
-fn parse_input(input: &str) -> Result<Vec<Segment>> {
+fn parse_segment(index: usize, token: &str) -> Result<Segment> {
+    let mut chars = token.chars();
+    let direction = match chars.next().map(|c| c.to_ascii_uppercase()) {
+        Some('U') => Direction::Up,
+        Some('D') => Direction::Down,
+        Some('L') => Direction::Left,
+        Some('R') => Direction::Right,
+        Some(_) => return Err(format!("segment {} ({:?}): invalid direction", index, token).into()),
+        None => return Err(format!("segment {}: empty segment", index).into())
+    };
 
-    let path: Vec<Segment> = input
+    let length = chars.as_str().parse::<usize>()
+        .map_err(|e| format!("segment {} ({:?}): invalid length: {}", index, token, e))?;
+    if length == 0 {
+        return Err(format!("segment {} ({:?}): zero length", index, token).into());
+    }
+
+    Ok(Segment { direction, length })
+}
+
+//
+// Parses one wire. Directions may be lowercase, and blank tokens (from a
+// stray or trailing comma) are skipped; segment indices in errors still
+// count them.
+//
+fn parse_input(input: &str) -> Result<Vec<Segment>> {
+    let path = input
         .split(",")
-        .map(|s|
-             {
-                 let dir = s.chars().nth(0).ok_or("Invalid Input").unwrap();
-                 let len_str: String = s.chars().filter(|x| x.is_digit(10)).collect();
-                 let len: usize = len_str.parse::<usize>().unwrap();
-                 match dir {
-                     'U' => {
-                         Segment {
-                             direction: Direction::Up,
-                             length: len
-                         }
-                     }
-                     'D' => {
-                         Segment {
-                             direction: Direction::Down,
-                             length: len
-                         }
-                     }
-                     'L' => {
-                         Segment {
-                             direction: Direction::Left,
-                             length: len
-                         }
-                     }
-                     'R' => {
-                         Segment {
-                             direction: Direction::Right,
-                             length: len
-                         }
-                     }
-                     _ => {
-                         panic!("Invalid input!")
-                     }
-                 }
-             }
-        ).collect();
+        .map(|s| s.trim())
+        .enumerate()
+        .filter(|(_, s)| !s.is_empty())
+        .map(|(index, s)| parse_segment(index, s))
+        .collect::<Result<Vec<Segment>>>()?;
 
+    if path.is_empty() {
+        return Err("wire has no segments".into());
+    }
     Ok(path)
 }
 
+//
+// Parses the wires, one per non-blank line; any line ending works.
+//
+fn parse_wires(input: &str) -> Result<Vec<Vec<Segment>>> {
+    let lines: Vec<&str> = input.lines().filter(|line| !line.trim().is_empty()).collect();
+    if lines.len() < 2 {
+        return Err(format!("expected at least two wires, found {}", lines.len()).into());
+    }
+
+    lines.iter().enumerate()
+        .map(|(index, line)| parse_input(line).map_err(|e| format!("wire {}, {}", index + 1, e).into()))
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    //
+    // Every cell the wire passes through, as (y, x), mapped to the number of
+    // steps the wire takes to first reach it. A wire crossing itself keeps the
+    // earlier count, and the origin is left out even if the wire comes back
+    // through it.
+    //
+    fn first_visits(path: &Vec<Segment>) -> HashMap<(i32, i32), usize> {
+        let mut visits = HashMap::new();
+        let mut y: i32 = 0;
+        let mut x: i32 = 0;
+        let mut steps = 0;
+
+        for s in path {
+            for _ in 0..s.length {
+                y += s.direction.value().0 as i32;
+                x += s.direction.value().1 as i32;
+                steps += 1;
+                if (y, x) != (0, 0) {
+                    visits.entry((y, x)).or_insert(steps);
+                }
+            }
+        }
+
+        visits
+    }
+
+    //
+    // Original cell-by-cell solutions, kept to check the segment based ones
+    // against.
+    //
+    fn part1_points(path0: &Vec<Segment>, path1: &Vec<Segment>) -> Result<i32>
+    {
+        // based off https://github.com/Ummon/AdventOfCode2019/blob/master/src/day03.rs
+        let visits0 = first_visits(path0);
+        let visits1 = first_visits(path1);
+
+        visits0.keys()
+            .filter(|pos| visits1.contains_key(pos))
+            .map(|(y, x)| y.abs() + x.abs())
+            .min()
+            .ok_or("Wires never cross".into())
+    }
+
+    fn part2_points(path0: &Vec<Segment>, path1: &Vec<Segment>) -> Result<i32>
+    {
+        let visits0 = first_visits(path0);
+        let visits1 = first_visits(path1);
+
+        visits0.iter()
+            .filter_map(|(pos, steps0)| visits1.get(pos).map(|steps1| (steps0 + steps1) as i32))
+            .min()
+            .ok_or("Wires never cross".into())
+    }
+
     #[test]
     fn test_part1(){
-        let path0 = parse_input("R8,U5,L5,D3").unwrap();
-        let path1 = parse_input("U7,R6,D4,L4").unwrap();
-        assert_eq!(part1(&path0, &path1).unwrap(), 6);
+        let wires = [parse_input("R8,U5,L5,D3").unwrap(),
+                     parse_input("U7,R6,D4,L4").unwrap()];
+        assert_eq!(part1(&wires).unwrap(), 6);
 
-        let path0 = parse_input("R75,D30,R83,U83,L12,D49,R71,U7,L72").unwrap();
-        let path1 = parse_input("U62,R66,U55,R34,D71,R55,D58,R83").unwrap();
-        assert_eq!(part1(&path0, &path1).unwrap(), 159);
+        let wires = [parse_input("R75,D30,R83,U83,L12,D49,R71,U7,L72").unwrap(),
+                     parse_input("U62,R66,U55,R34,D71,R55,D58,R83").unwrap()];
+        assert_eq!(part1(&wires).unwrap(), 159);
+
+        let wires = [parse_input("R98,U47,R26,D63,R33,U87,L62,D20,R33,U53,R51").unwrap(),
+                     parse_input("U98,R91,D20,R16,D67,R40,U7,R15,U6,R7").unwrap()];
+        assert_eq!(part1(&wires).unwrap(), 135);
+    }
+
+    fn closest(path0: &str, path1: &str) -> Option<i64> {
+        part1(&[parse_input(path0).unwrap(), parse_input(path1).unwrap()]).ok()
+    }
 
-        let path0 = parse_input("R98,U47,R26,D63,R33,U87,L62,D20,R33,U53,R51").unwrap();
-        let path1 = parse_input("U98,R91,D20,R16,D67,R40,U7,R15,U6,R7").unwrap();
-        assert_eq!(part1(&path0, &path1).unwrap(), 135);
+    fn fewest_steps(path0: &str, path1: &str) -> Option<i64> {
+        part2(&[parse_input(path0).unwrap(), parse_input(path1).unwrap()]).ok()
+    }
+
+    fn intersection_list(path0: &str, path1: &str) -> Vec<(i64, i64, i64, i64)> {
+        intersections(&parse_input(path0).unwrap(), &parse_input(path1).unwrap()).iter()
+            .map(|i| (i.x, i.y, i.manhattan, i.combined_steps))
+            .collect()
+    }
+
+    #[test]
+    fn test_intersections() {
+        // (x, y, manhattan, combined steps), with up being negative y
+        assert_eq!(intersection_list("R8,U5,L5,D3", "U7,R6,D4,L4"),
+                   [(3, -3, 6, 40), (6, -5, 11, 30)]);
+        assert_eq!(intersection_list("R75,D30,R83,U83,L12,D49,R71,U7,L72", "U62,R66,U55,R34,D71,R55,D58,R83"),
+                   [(155, -4, 159, 726), (155, -11, 166, 850), (158, 12, 170, 610), (146, -46, 192, 624)]);
+        assert_eq!(intersection_list("R98,U47,R26,D63,R33,U87,L62,D20,R33,U53,R51", "U98,R91,D20,R16,D67,R40,U7,R15,U6,R7"),
+                   [(124, -11, 135, 516), (107, -47, 154, 410), (107, -51, 158, 700), (157, -18, 175, 650), (107, -71, 178, 636)]);
+
+        // overlapping wires share every cell of the overlap, each reported once
+        assert_eq!(intersection_list("R3,L3", "R2"), [(1, 0, 1, 2), (2, 0, 2, 4)]);
+    }
+
+    #[test]
+    fn test_matches_points() {
+        let samples = [
+            ("R8,U5,L5,D3", "U7,R6,D4,L4"),
+            ("R75,D30,R83,U83,L12,D49,R71,U7,L72", "U62,R66,U55,R34,D71,R55,D58,R83"),
+            ("R98,U47,R26,D63,R33,U87,L62,D20,R33,U53,R51", "U98,R91,D20,R16,D67,R40,U7,R15,U6,R7"),
+            ("R10", "R3"),
+            ("U2,R10", "R5,U2,L8"),
+            ("L4,U3,R9", "U3,L6,R9")
+        ];
+
+        for (path0, path1) in samples.iter() {
+            let wires = [parse_input(path0).unwrap(), parse_input(path1).unwrap()];
+            assert_eq!(part1(&wires).unwrap(), part1_points(&wires[0], &wires[1]).unwrap() as i64);
+            assert_eq!(part2(&wires).unwrap(), part2_points(&wires[0], &wires[1]).unwrap() as i64);
+        }
+    }
+
+    #[test]
+    fn test_intersections_overlap() {
+        assert_eq!(closest("R10", "R3"), Some(1));
+        assert_eq!(closest("U2,R10", "R5,U2,L8"), Some(2));
+        assert_eq!(closest("R10", "L10"), None);
+    }
+
+    #[test]
+    fn test_intersections_long_wires() {
+        assert_eq!(closest("R10000000,U10000000", "U5000000,R20000000"), Some(15000000));
+        assert_eq!(fewest_steps("R10000000,U10000000", "U5000000,R20000000"), Some(30000000));
+
+        assert_eq!(closest("R100000,U100000,L50000,D200000", "U50000,R200000"), Some(100000));
+        assert_eq!(fewest_steps("R100000,U100000,L50000,D200000", "U50000,R200000"), Some(300000));
+    }
+
+    #[test]
+    fn test_self_crossing() {
+        // the first wire passes (0, 3) after 3 steps, loops around and
+        // crosses itself there after 11; the second wire reaches it after 5
+        let wires = [parse_input("R5,U2,L2,D4").unwrap(),
+                     parse_input("D1,R3,U1").unwrap()];
+        assert_eq!(part1(&wires).unwrap(), 3);
+        assert_eq!(part1_points(&wires[0], &wires[1]).unwrap(), 3);
+        assert_eq!(part2(&wires).unwrap(), 8);
+        assert_eq!(part2_points(&wires[0], &wires[1]).unwrap(), 8);
+
+        // a wire crossing only itself doesn't count
+        let wires = [parse_input("R5,U2,L2,D4").unwrap(),
+                     parse_input("L5").unwrap()];
+        assert!(part1(&wires).is_err());
+        assert!(part1_points(&wires[0], &wires[1]).is_err());
+        assert!(part2_points(&wires[0], &wires[1]).is_err());
+    }
+
+    #[test]
+    fn test_origin_excluded() {
+        // both wires pass back through the origin
+        let wires = [parse_input("R2,L4").unwrap(),
+                     parse_input("L3,R5").unwrap()];
+        assert_eq!(part1(&wires).unwrap(), 1);
+        assert_eq!(part1_points(&wires[0], &wires[1]).unwrap(), 1);
+        assert_eq!(part2(&wires).unwrap(), part2_points(&wires[0], &wires[1]).unwrap() as i64);
+    }
+
+    #[test]
+    fn test_overlap_steps() {
+        // both wires run right together from x = 5 to 8; the combined
+        // steps are smallest at the start of the overlap
+        assert_eq!(fewest_steps("R8", "U1,R5,D1,R3"), Some(12));
+        // running in opposite directions, every cell of the overlap costs
+        // the same
+        assert_eq!(fewest_steps("R8", "U1,R8,D1,L8"), Some(18));
+    }
+
+    fn parse_error(input: &str) -> String {
+        parse_input(input).err().unwrap().to_string()
+    }
+
+    #[test]
+    fn test_parse_input_errors() {
+        assert_eq!(parse_error(""), "wire has no segments");
+        assert_eq!(parse_error(" , "), "wire has no segments");
+        assert_eq!(parse_error("R8,R"), "segment 1 (\"R\"): invalid length: cannot parse integer from empty string");
+        assert_eq!(parse_error("U5x"), "segment 0 (\"U5x\"): invalid length: invalid digit found in string");
+        assert_eq!(parse_error("R8,L-2"), "segment 1 (\"L-2\"): invalid length: invalid digit found in string");
+        assert_eq!(parse_error("Z5"), "segment 0 (\"Z5\"): invalid direction");
+        assert_eq!(parse_error("R8,,z5"), "segment 2 (\"z5\"): invalid direction");
+        assert_eq!(parse_error("U5,D0"), "segment 1 (\"D0\"): zero length");
+    }
+
+    #[test]
+    fn test_parse_wires() {
+        let canonical = parse_wires("R75,D30,R83,U83,L12,D49,R71,U7,L72\nU62,R66,U55,R34,D71,R55,D58,R83\n").unwrap();
+        assert_eq!(part1(&canonical).unwrap(), 159);
+        assert_eq!(part2(&canonical).unwrap(), 610);
+
+        let sloppy = parse_wires("r75,d30,r83,u83,l12,d49,r71,u7,l72,\r\n\r\nU62, r66,u55,R34,d71,r55,d58,r83\r\n").unwrap();
+        assert_eq!(part1(&sloppy).unwrap(), 159);
+        assert_eq!(part2(&sloppy).unwrap(), 610);
+
+        let error = |input| parse_wires(input).err().unwrap().to_string();
+        assert_eq!(error("R8,U5\n"), "expected at least two wires, found 1");
+        assert_eq!(error("\n"), "expected at least two wires, found 0");
+        assert_eq!(error("R8\nU5,X3\n"), "wire 2, segment 1 (\"X3\"): invalid direction");
+        assert_eq!(error("R8\nU5\nL2,D0\n"), "wire 3, segment 1 (\"D0\"): zero length");
+
+        assert_eq!(parse_wires("R8\nU5\nL2\n").unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_parse_input() {
+        assert_eq!(parse_input("R8,U5\n").unwrap().len(), 2);
+        assert_eq!(parse_input("R8,,U5,").unwrap().len(), 2);
+        assert_eq!(parse_input("R75,D30,R83,U83,L12,D49,R71,U7,L72").unwrap().len(), 9);
+
+        let path = parse_input(" U7, R6,D4 ,L4").unwrap();
+        let lengths: Vec<usize> = path.iter().map(|s| s.length).collect();
+        assert_eq!(lengths, [7, 6, 4, 4]);
     }
 
     #[test]
     fn test_part2() {
-        let path0 = parse_input("R8,U5,L5,D3").unwrap();
-        let path1 = parse_input("U7,R6,D4,L4").unwrap();
-        assert_eq!(part2(&path0, &path1).unwrap(), 30);
+        let wires = [parse_input("R8,U5,L5,D3").unwrap(),
+                     parse_input("U7,R6,D4,L4").unwrap()];
+        assert_eq!(part2(&wires).unwrap(), 30);
+
+        let wires = [parse_input("R75,D30,R83,U83,L12,D49,R71,U7,L72").unwrap(),
+                     parse_input("U62,R66,U55,R34,D71,R55,D58,R83").unwrap()];
+        assert_eq!(part2(&wires).unwrap(), 610);
+
+        let wires = [parse_input("R98,U47,R26,D63,R33,U87,L62,D20,R33,U53,R51").unwrap(),
+                     parse_input("U98,R91,D20,R16,D67,R40,U7,R15,U6,R7").unwrap()];
+        assert_eq!(part2(&wires).unwrap(), 410);
+    }
+
+    #[test]
+    fn test_intersection_count() {
+        let count = |path0, path1| intersection_count(&[parse_input(path0).unwrap(), parse_input(path1).unwrap()]);
+
+        assert_eq!(count("R8,U5,L5,D3", "U7,R6,D4,L4"), 2);
+        assert_eq!(count("R75,D30,R83,U83,L12,D49,R71,U7,L72", "U62,R66,U55,R34,D71,R55,D58,R83"), 4);
+        // the origin is shared but not counted
+        assert_eq!(count("R10", "L10"), 0);
+        // (x 3, y 0) is visited twice by each wire, and the first wire
+        // also crosses itself there
+        assert_eq!(count("R5,U2,L2,D4", "D1,R3,U2,D2"), 3);
+    }
+
+    #[test]
+    fn test_render_svg() {
+        let render = |path0, path1| {
+            let wires = [parse_input(path0).unwrap(), parse_input(path1).unwrap()];
+            render_svg(&wires, &crossings(&wires))
+        };
+
+        let svg = render("R8,U5,L5,D3", "U7,R6,D4,L4");
+        assert_eq!(svg.matches("<circle").count(), 2);
+        assert_eq!(svg.matches("<polyline").count(), 2);
+        assert!(svg.contains("viewBox=\"-1 -8 10 9\""));
+        assert!(svg.contains("points=\"0,0 8,0 8,-5 3,-5 3,-2\""));
+        assert!(svg.contains("cx=\"3\" cy=\"-3\" fill=\"green\""));
+        assert!(svg.contains("cx=\"6\" cy=\"-5\" fill=\"orange\""));
+
+        let svg = render("R75,D30,R83,U83,L12,D49,R71,U7,L72", "U62,R66,U55,R34,D71,R55,D58,R83");
+        assert_eq!(svg.matches("<circle").count(), 4);
+        assert_eq!(svg.matches("fill=\"none\" r=").count(), 2);
+
+        let svg = render("R98,U47,R26,D63,R33,U87,L62,D20,R33,U53,R51", "U98,R91,D20,R16,D67,R40,U7,R15,U6,R7");
+        assert_eq!(svg.matches("<circle").count(), 5);
+
+        assert_eq!(render("R10", "L10").matches("<circle").count(), 0);
+    }
+
+    #[test]
+    fn test_three_wires() {
+        // the first wire only meets the second far out at x 25, while the
+        // second and third cross at (x 2, y -2)
+        let wires = parse_wires("D3,R25,U10\nU2,R30\nR2,U5\n").unwrap();
+        let list: Vec<(i64, i64, i64, i64)> = crossings(&wires).iter()
+            .map(|i| (i.x, i.y, i.manhattan, i.combined_steps))
+            .collect();
+        assert_eq!(list, [(2, -2, 4, 8), (25, -2, 27, 60)]);
+        assert_eq!(part1(&wires).unwrap(), 4);
+        assert_eq!(part2(&wires).unwrap(), 8);
+        assert_eq!(intersection_count(&wires), 2);
+
+        // the pairs taken on their own agree
+        assert_eq!(part1(&wires[1..]).unwrap(), 4);
+        assert_eq!(part1(&wires[..2]).unwrap(), 27);
+        assert!(part1(&[parse_input("D3,R25,U10").unwrap(), parse_input("R2,U5").unwrap()]).is_err());
+
+        // all three wires cross at (x 5, y -1); the first two get there
+        // in 12 steps, the others in 16
+        let wires = parse_wires("R5,U5\nU1,R10\nU3,R5,D3\n").unwrap();
+        let cell = crossings(&wires).into_iter().find(|i| (i.x, i.y) == (5, -1)).unwrap();
+        assert_eq!(cell.combined_steps, 12);
+        assert_eq!(render_svg(&wires, &crossings(&wires)).matches("<polyline").count(), 3);
+    }
+
+    #[test]
+    fn test_first_visits() {
+        // (x 1, y 0) is passed after 1, 5 and 7 steps before the wire ends
+        // back at the origin; only the first arrival is kept
+        let wire = parse_input("R2,U1,L1,D2,U1,L1").unwrap();
+        let visits = first_visits(&wire);
+        assert_eq!(visits.len(), 5);
+        assert_eq!(visits[&(0, 1)], 1);
+        assert_eq!(visits[&(1, 1)], 6);
+        assert!(!visits.contains_key(&(0, 0)));
+
+        // counting the last arrival would give 7 + 3 instead
+        let wires = [wire, parse_input("D1,R1,U1").unwrap()];
+        assert_eq!(part2(&wires).unwrap(), 4);
+        assert_eq!(part2_points(&wires[0], &wires[1]).unwrap(), 4);
+    }
+
+    #[test]
+    fn test_revisited_intersection() {
+        // both wires come back through (x 3, y 0): the first after 3 and
+        // 11 steps, the second after 5 and 7; only the earliest visits count
+        let wires = [parse_input("R5,U2,L2,D4").unwrap(),
+                     parse_input("D1,R3,U2,D2").unwrap()];
 
-        let path0 = parse_input("R75,D30,R83,U83,L12,D49,R71,U7,L72").unwrap();
-        let path1 = parse_input("U62,R66,U55,R34,D71,R55,D58,R83").unwrap();
-        assert_eq!(part2(&path0, &path1).unwrap(), 610);
+        let visits0 = first_visits(&wires[0]);
+        let visits1 = first_visits(&wires[1]);
+        assert_eq!((visits0[&(0, 3)], visits1[&(0, 3)]), (3, 5));
 
-        let path0 = parse_input("R98,U47,R26,D63,R33,U87,L62,D20,R33,U53,R51").unwrap();
-        let path1 = parse_input("U98,R91,D20,R16,D67,R40,U7,R15,U6,R7").unwrap();
-        assert_eq!(part2(&path0, &path1).unwrap(), 410);
+        assert_eq!(intersection_list("R5,U2,L2,D4", "D1,R3,U2,D2"),
+                   [(3, 0, 3, 8), (3, -1, 4, 16), (3, 1, 4, 16)]);
+        assert_eq!(part2(&wires).unwrap(), 8);
+        assert_eq!(part2_points(&wires[0], &wires[1]).unwrap(), 8);
     }
 }
@@ -0,0 +1,190 @@
+//!
+//! Digit run checks for the day 4 passwords, shared by the solver in
+//! main.rs and anything else that wants to vet a password, and a counter
+//! for ranges too wide to check number by number. Passwords are given as
+//! their digits, most significant first.
+//!
+
+use std::collections::HashMap;
+
+///
+/// Whether some digit repeats exactly `k` times in a row, not as part of a
+/// longer run. Part 2's rule is `k = 2`.
+///
+/// ```
+/// use aoc_2019_04::has_run_of_exactly;
+///
+/// assert!(has_run_of_exactly(&[1, 1, 2, 2, 3, 3], 2));
+/// assert!(!has_run_of_exactly(&[1, 2, 3, 4, 4, 4], 2));
+/// ```
+///
+pub fn has_run_of_exactly(digits: &[u8], k: usize) -> bool {
+    digits.chunk_by(|a, b| a == b).any(|run| run.len() == k)
+}
+
+///
+/// Whether some digit repeats at least `k` times in a row. Part 1's rule
+/// is `k = 2`.
+///
+pub fn has_run_of_at_least(digits: &[u8], k: usize) -> bool {
+    digits.chunk_by(|a, b| a == b).any(|run| run.len() >= k)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DupRule {
+    AtLeast(usize),
+    Exactly(usize)
+}
+
+impl DupRule {
+    pub const PART1: DupRule = DupRule::AtLeast(2);
+    pub const PART2: DupRule = DupRule::Exactly(2);
+
+    pub fn matches(&self, digits: &[u8]) -> bool {
+        match *self {
+            DupRule::AtLeast(k) => has_run_of_at_least(digits, k),
+            DupRule::Exactly(k) => has_run_of_exactly(digits, k)
+        }
+    }
+}
+
+///
+/// Counts the passwords in min..=max without visiting them, by counting
+/// non-decreasing digit strings digit by digit. Besides the previous
+/// digit, all that matters is the length of the current run of equal
+/// digits (1, 2 or more) and whether an earlier run already made the pair;
+/// with `exact_pair` only a run of exactly two does, as in part 2. Numbers
+/// of any length are counted, which makes this the way to experiment with
+/// ranges too wide to walk through.
+///
+pub fn count_valid(min: u64, max: u64, exact_pair: bool) -> u64 {
+    if min > max {
+        return 0;
+    }
+
+    let mut counter = DigitCounter { exact_pair, memo: HashMap::new() };
+    let below_min = if min == 0 { 0 } else { counter.count_up_to(min - 1) };
+    counter.count_up_to(max) - below_min
+}
+
+//
+// Digits placed so far: the last one, how long its run is (capped at 3,
+// 0 before the first digit) and whether a finished run made a pair.
+//
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+struct Prefix {
+    last: u8,
+    run: u8,
+    paired: bool
+}
+
+struct DigitCounter {
+    exact_pair: bool,
+    memo: HashMap<(usize, Prefix), u64>
+}
+
+impl DigitCounter {
+    fn is_pair(&self, run: u8) -> bool {
+        if self.exact_pair { run == 2 } else { run >= 2 }
+    }
+
+    fn push(&self, prefix: Prefix, digit: u8) -> Prefix {
+        if prefix.run > 0 && digit == prefix.last {
+            Prefix { run: u8::min(prefix.run + 1, 3), ..prefix }
+        } else {
+            Prefix { last: digit, run: 1, paired: prefix.paired || self.is_pair(prefix.run) }
+        }
+    }
+
+    //
+    // Number of ways to finish `prefix` with `remaining` more digits, with
+    // no upper bound on them.
+    //
+    fn complete(&mut self, remaining: usize, prefix: Prefix) -> u64 {
+        if remaining == 0 {
+            return (prefix.paired || self.is_pair(prefix.run)) as u64;
+        }
+        if let Some(&count) = self.memo.get(&(remaining, prefix)) {
+            return count;
+        }
+
+        let count = (prefix.last..=9)
+            .map(|digit| {
+                let next = self.push(prefix, digit);
+                self.complete(remaining - 1, next)
+            })
+            .sum();
+        self.memo.insert((remaining, prefix), count);
+        count
+    }
+
+    //
+    // Valid passwords in 1..=n. Shorter numbers are unbounded; those as
+    // long as n follow its digits for as long as they stay non-decreasing,
+    // branching off below the next digit of n at every step.
+    //
+    fn count_up_to(&mut self, n: u64) -> u64 {
+        let digits: Vec<u8> = n.to_string().bytes().map(|b| b - b'0').collect();
+        let start = Prefix { last: 1, run: 0, paired: false };
+        let mut total = 0;
+
+        for length in 1..digits.len() {
+            total += self.complete(length, start);
+        }
+
+        let mut prefix = start;
+        for (index, &limit) in digits.iter().enumerate() {
+            let remaining = digits.len() - index - 1;
+            for digit in prefix.last..limit {
+                let next = self.push(prefix, digit);
+                total += self.complete(remaining, next);
+            }
+            if limit < prefix.last {
+                return total;
+            }
+            prefix = self.push(prefix, limit);
+        }
+
+        total + self.complete(0, prefix)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_runs() {
+        // digits, k, has a run of exactly k, has a run of at least k
+        let cases: [(&[u8], usize, bool, bool); 15] = [
+            (&[1, 1, 2, 3, 4, 5], 2, true, true),
+            (&[1, 2, 2, 3, 4, 5], 2, true, true),
+            (&[1, 2, 3, 4, 5, 5], 2, true, true),
+            (&[1, 1, 1, 2, 3, 4], 2, false, true),
+            (&[1, 2, 3, 4, 5, 6], 2, false, false),
+            (&[1, 1, 1, 2, 3, 4], 3, true, true),
+            (&[1, 2, 2, 2, 3, 4], 3, true, true),
+            (&[1, 2, 3, 4, 4, 4], 3, true, true),
+            (&[1, 1, 1, 1, 2, 3], 3, false, true),
+            (&[1, 1, 2, 2, 3, 3], 3, false, false),
+            (&[1, 1, 1, 1, 2, 3], 4, true, true),
+            (&[1, 2, 2, 2, 2, 3], 4, true, true),
+            (&[1, 2, 3, 3, 3, 3], 4, true, true),
+            (&[1, 1, 1, 1, 1, 1], 4, false, true),
+            (&[1, 1, 1, 2, 2, 2], 4, false, false)
+        ];
+
+        for (digits, k, exactly, at_least) in cases.iter() {
+            assert_eq!(has_run_of_exactly(digits, *k), *exactly, "{:?} exactly {}", digits, k);
+            assert_eq!(has_run_of_at_least(digits, *k), *at_least, "{:?} at least {}", digits, k);
+        }
+    }
+
+    #[test]
+    fn test_dup_rule() {
+        assert!(DupRule::PART1.matches(&[1, 1, 1, 2, 3, 4]));
+        assert!(!DupRule::PART2.matches(&[1, 1, 1, 2, 3, 4]));
+        assert!(DupRule::PART2.matches(&[1, 1, 1, 1, 2, 2]));
+        assert!(!DupRule::Exactly(2).matches(&[]));
+    }
+}
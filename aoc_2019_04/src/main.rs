@@ -1,116 +1,394 @@
-fn main() {
-    println!("{}", part1_brute(402328, 864247));
-    println!("{}", part2(402328, 864247));
-}
+use std::io::Read;
 
-fn is_monotonic(password: &str) -> bool {
-    let mut prev_char = '0';
+use aoc_2019_04::{has_run_of_at_least, has_run_of_exactly, DupRule};
 
-    for c in password.chars() {
-        if c < prev_char {
-            return false;
+type Result<T> = ::std::result::Result<T, Box<dyn ::std::error::Error>>;
+
+//
+// The range is taken from the first argument, e.g. `402328-864247`, or
+// read from stdin in the same format. `--run-exactly k` or
+// `--run-at-least k` counts the passwords with such a run of repeated
+// digits instead of solving both parts.
+//
+fn main() -> Result<()> {
+    let mut range = None;
+    let mut rule = None;
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--run-exactly" | "--run-at-least" => {
+                let k = args.next().ok_or_else(|| format!("{} needs a run length", arg))?;
+                let k: usize = k.parse().map_err(|e| format!("invalid run length {:?}: {}", k, e))?;
+                rule = Some(if arg == "--run-exactly" { DupRule::Exactly(k) } else { DupRule::AtLeast(k) });
+            },
+            _ => range = Some(arg)
         }
-        prev_char = c;
     }
 
-    true
-}
-fn has_duplicate_digit(password: &str) -> bool {
-    let mut prev_char = 'a';
+    let range = match range {
+        Some(range) => range,
+        None => {
+            let mut input = String::new();
+            std::io::stdin().read_to_string(&mut input)?;
+            input
+        }
+    };
 
-    for c in password.chars() {
-        if c == prev_char {
-            return true;
+    let (min, max) = parse_range(&range)?;
+    match rule {
+        Some(rule) => {
+            let (min, max) = password_range(min, max);
+            println!("{}", ValidPasswords::new(min, max, rule).count());
+        },
+        None => {
+            let (part1, part2) = count_both(min, max);
+            println!("{}", part1);
+            println!("{}", part2);
         }
-        prev_char = c;
     }
+    Ok(())
+}
+
+fn parse_range(input: &str) -> Result<(u32, u32)> {
+    let (min, max) = input.trim().split_once('-')
+        .ok_or_else(|| format!("expected a range like 402328-864247, got {:?}", input.trim()))?;
+    let min: u32 = min.trim().parse().map_err(|e| format!("invalid range start {:?}: {}", min.trim(), e))?;
+    let max: u32 = max.trim().parse().map_err(|e| format!("invalid range end {:?}: {}", max.trim(), e))?;
+
+    if min == 0 {
+        return Err("range must start above zero".into());
+    }
+    if min > max {
+        return Err(format!("range start {} is after its end {}", min, max).into());
+    }
+    Ok((min, max))
+}
+
+//
+// Number of passwords in the range for part 1 and part 2, in one pass
+// over the range's non-decreasing numbers. Every part 2 password also
+// passes part 1, so only part 1's passwords are checked against part 2.
+//
+fn count_both(min: u32, max: u32) -> (u32, u32) {
+    let (min, max) = password_range(min, max);
+    let mut buffer = [0; 10];
+
+    ValidPasswords::new(min, max, DupRule::PART1).fold((0, 0), |(part1, part2), password| {
+        let exact_pair = DupRule::PART2.matches(to_digits(password, &mut buffer));
+        (part1 + 1, part2 + exact_pair as u32)
+    })
+}
 
-    false
+//
+// Passwords are as long as the range's upper bound (six digits for the
+// puzzle), so shorter numbers at the start of the range are skipped.
+//
+fn password_range(min: u32, max: u32) -> (u32, u32) {
+    let length = digit_count(max);
+    (u32::max(min, 10u32.pow(length as u32 - 1)), max)
 }
 
-fn is_valid(password: u32) -> bool {
-    let password_str: String = password.to_string();
+fn digit_count(password: u32) -> usize {
+    let mut count = 1;
+    let mut rest = password / 10;
+    while rest > 0 {
+        count += 1;
+        rest /= 10;
+    }
+    count
+}
 
-    if password_str.chars().count() != 6 {
-        false
-    } else if is_monotonic(&password_str) == false {
-        false
-    } else if has_duplicate_digit(&password_str) == false {
-        false
-    } else {
-        true
+//
+// Writes the digits of `password` into `buffer`, most significant first,
+// and returns the part of it holding them.
+//
+fn to_digits(password: u32, buffer: &mut [u8; 10]) -> &[u8] {
+    let count = digit_count(password);
+    let mut rest = password;
+    for digit in buffer[..count].iter_mut().rev() {
+        *digit = (rest % 10) as u8;
+        rest /= 10;
     }
+    &buffer[..count]
 }
 
-fn part1_brute(min: u32, max: u32) -> u32 {
-    let mut tr: u32 = 0;
+fn is_monotonic(password: &[u8]) -> bool {
+    let mut prev_digit = 0;
 
-    for i in min..(max + 1) {
-        if is_valid(i) {
-            tr = tr + 1;
+    for &d in password {
+        if d < prev_digit {
+            return false;
         }
+        prev_digit = d;
     }
 
-    tr
+    true
+}
+
+//
+// The original digit by digit checks, kept to test the faster versions
+// against.
+//
+#[allow(dead_code)]
+fn is_valid(password: u32, length: usize) -> bool {
+    let mut buffer = [0; 10];
+    let digits = to_digits(password, &mut buffer);
+
+    digits.len() == length && is_monotonic(digits) && has_run_of_at_least(digits, 2)
+}
+
+#[allow(dead_code)]
+fn part1_brute(min: u32, max: u32) -> u32 {
+    let (min, max) = password_range(min, max);
+    ValidPasswords::new(min, max, DupRule::PART1).count() as u32
+}
+
+#[allow(dead_code)]
+fn is_valid_part2(password: u32, length: usize) -> bool {
+    let mut buffer = [0; 10];
+    let digits = to_digits(password, &mut buffer);
+
+    digits.len() == length && is_monotonic(digits) && has_run_of_exactly(digits, 2)
 }
 
-enum RunningState {
-    NotRunning,
-    OneDup,
-    BadDup
+#[allow(dead_code)]
+fn part2(min: u32, max: u32) -> u32 {
+    let (min, max) = password_range(min, max);
+    ValidPasswords::new(min, max, DupRule::PART2).count() as u32
 }
 
-fn has_duplicate_digit_part2(password: &str) -> bool {
-    let mut prev_char = 'a';
-    let mut prev_char_running: RunningState = RunningState::NotRunning;
-
-    for c in password.chars() {
-        if c == prev_char {
-            match prev_char_running {
-                RunningState::NotRunning => {
-                    prev_char_running = RunningState::OneDup;
-                },
-                _ => {
-                    prev_char_running = RunningState::BadDup;
-                }
+//
+// Valid passwords in min..=max, in increasing order. Only numbers with
+// non-decreasing digits are looked at: on a descent, the rest of the
+// number is filled with the digit before it, the smallest candidate
+// above it (after 129 comes 133). Like count_valid, numbers of any length
+// are yielded.
+//
+struct ValidPasswords {
+    next: u64,
+    max: u64,
+    rule: DupRule
+}
+
+impl ValidPasswords {
+    fn new(min: u32, max: u32, rule: DupRule) -> ValidPasswords {
+        ValidPasswords { next: min as u64, max: max as u64, rule }
+    }
+}
+
+impl Iterator for ValidPasswords {
+    type Item = u32;
+
+    fn next(&mut self) -> Option<u32> {
+        loop {
+            let mut digits: Vec<u8> = self.next.to_string().bytes().map(|b| b - b'0').collect();
+            if let Some(descent) = (1..digits.len()).find(|&i| digits[i] < digits[i - 1]) {
+                let fill = digits[descent - 1];
+                digits[descent..].iter_mut().for_each(|digit| *digit = fill);
             }
-        } else {
-            match prev_char_running {
-                RunningState::OneDup => { return true; }
-                _ => { prev_char_running = RunningState::NotRunning; }
+
+            let candidate = digits.iter().fold(0, |n, &digit| n * 10 + digit as u64);
+            if candidate > self.max {
+                self.next = candidate;
+                return None;
+            }
+
+            self.next = candidate + 1;
+            if self.rule.matches(&digits) {
+                return Some(candidate as u32);
             }
         }
-        prev_char = c;
     }
+}
 
-    match prev_char_running {
-        RunningState::OneDup => true,
-        _ => false
+#[cfg(test)]
+mod test {
+    use super::*;
+    use aoc_2019_04::count_valid;
+    use proptest::prelude::*;
+
+    //
+    // The three ways of counting the passwords in min..=max, which must all
+    // agree: checking every number, walking the non-decreasing ones, and the
+    // digit counter, which only knows the two puzzle rules. None of them
+    // limit the password length.
+    //
+    type Counter = fn(u32, u32, DupRule) -> u64;
+
+    const COUNTERS: [(&str, Counter); 3] = [
+        ("brute force", count_brute),
+        ("iterator", count_iterator),
+        ("digit counter", count_digits)
+    ];
+
+    fn is_password(password: u32, rule: DupRule) -> bool {
+        let mut buffer = [0; 10];
+        let digits = to_digits(password, &mut buffer);
+        is_monotonic(digits) && rule.matches(digits)
+    }
+
+    fn count_brute(min: u32, max: u32, rule: DupRule) -> u64 {
+        (min..=max).filter(|&password| is_password(password, rule)).count() as u64
     }
-}
 
-fn is_valid_part2(password: u32) -> bool {
-    let password_str: String = password.to_string();
+    fn count_iterator(min: u32, max: u32, rule: DupRule) -> u64 {
+        ValidPasswords::new(min, max, rule).count() as u64
+    }
 
-    if password_str.chars().count() != 6 {
-        false
-    } else if is_monotonic(&password_str) == false {
-        false
-    } else if has_duplicate_digit_part2(&password_str) == false {
-        false
-    } else {
-        true
+    fn count_digits(min: u32, max: u32, rule: DupRule) -> u64 {
+        assert!(rule == DupRule::PART1 || rule == DupRule::PART2, "the digit counter has no rule {:?}", rule);
+        count_valid(min as u64, max as u64, rule == DupRule::PART2)
     }
-}
 
-fn part2(min: u32, max: u32) -> u32{
-    let mut tr: u32 = 0;
+    #[test]
+    fn test_count_valid() {
+        assert_eq!(count_valid(402328, 864247, false), part1_brute(402328, 864247) as u64);
+        assert_eq!(count_valid(402328, 864247, true), part2(402328, 864247) as u64);
+        assert_eq!(count_valid(402328, 864247, false), 454);
+        assert_eq!(count_valid(402328, 864247, true), 288);
+
+        // bounds that are valid passwords themselves are included
+        assert_eq!(count_valid(111111, 111111, false), 1);
+        assert_eq!(count_valid(111111, 111111, true), 0);
+        assert_eq!(count_valid(112233, 112233, true), 1);
+        assert_eq!(count_valid(864247, 402328, false), 0);
+
+        // every length is counted: 11, 22, .., 99 and then 111
+        assert_eq!(count_valid(0, 111, false), 10);
+        assert_eq!(count_valid(0, 111, true), 9);
+    }
+
+    #[test]
+    fn test_parse_range() {
+        assert_eq!(parse_range("402328-864247").unwrap(), (402328, 864247));
+        assert_eq!(parse_range("  402328 - 864247\n").unwrap(), (402328, 864247));
+        assert_eq!(parse_range("5-5").unwrap(), (5, 5));
+
+        let error = |input| parse_range(input).unwrap_err().to_string();
+        assert_eq!(error("864247-402328"), "range start 864247 is after its end 402328");
+        assert_eq!(error("0-10"), "range must start above zero");
+        assert_eq!(error("402328"), "expected a range like 402328-864247, got \"402328\"");
+        assert_eq!(error("-5-10"), "invalid range start \"\": cannot parse integer from empty string");
+        assert_eq!(error("1-x"), "invalid range end \"x\": invalid digit found in string");
+    }
+
+    #[test]
+    fn test_count_both() {
+        // 111122 and 111111 to 111119 pass part 1; only 111122 passes part 2
+        assert_eq!(count_both(111110, 111122), (10, 1));
+        assert_eq!(count_both(402328, 864247), (454, 288));
+        assert_eq!(count_both(402328, 864247), (part1_brute(402328, 864247), part2(402328, 864247)));
+        assert_eq!(count_both(402328, 864247), (count_valid(402328, 864247, false) as u32, count_valid(402328, 864247, true) as u32));
+        assert_eq!(count_both(864247, 402328), (0, 0));
+    }
+
+    #[test]
+    fn test_valid_passwords() {
+        let (min, max) = (402328, 864247);
+        assert_eq!(ValidPasswords::new(min, max, DupRule::PART1).count(), (min..=max).filter(|&p| is_valid(p, 6)).count());
+        assert_eq!(ValidPasswords::new(min, max, DupRule::PART2).count(), (min..=max).filter(|&p| is_valid_part2(p, 6)).count());
+        assert_eq!(part1_brute(min, max), 454);
+        assert_eq!(part2(min, max), 288);
+
+        let passwords: Vec<u32> = ValidPasswords::new(111110, 111125, DupRule::PART1).collect();
+        assert_eq!(passwords, [111111, 111112, 111113, 111114, 111115, 111116, 111117, 111118, 111119, 111122, 111123, 111124, 111125]);
+        let passwords: Vec<u32> = ValidPasswords::new(111110, 111125, DupRule::PART2).collect();
+        assert_eq!(passwords, [111122]);
+
+        // the first candidate above 402328 is 444444
+        assert_eq!(ValidPasswords::new(402328, 864247, DupRule::PART1).next(), Some(444444));
+        assert_eq!(ValidPasswords::new(402328, 444443, DupRule::PART1).next(), None);
+        assert_eq!(ValidPasswords::new(u32::MAX - 1, u32::MAX, DupRule::PART1).next(), None);
+        assert_eq!(ValidPasswords::new(0, 100, DupRule::PART1).collect::<Vec<u32>>(), [11, 22, 33, 44, 55, 66, 77, 88, 99]);
+    }
 
-    for i in min..(max + 1) {
-        if is_valid_part2(i) {
-            tr = tr + 1;
+    #[test]
+    fn test_password_lengths() {
+        // 1111 to 1119 and 1122 to 1129 pass part 1; part 2 drops the
+        // 111x run of three
+        assert_eq!(part1_brute(1100, 1130), 17);
+        assert_eq!(part2(1100, 1130), 8);
+        assert_eq!((1100..=1130).filter(|&p| is_valid(p, 4)).count(), 17);
+        assert_eq!(count_both(1100, 1130), (17, 8));
+
+        // likewise with eight digits, but now only 11111122 has a pair
+        // that isn't part of a longer run
+        assert_eq!(part1_brute(11111100, 11111130), 17);
+        assert_eq!(part2(11111100, 11111130), 1);
+        assert_eq!((11111100..=11111130).filter(|&p| is_valid_part2(p, 8)).count(), 1);
+        assert_eq!(count_both(11111100, 11111130), (17, 1));
+
+        // the length comes from the upper bound
+        assert_eq!(password_range(50, 1130), (1000, 1130));
+        assert_eq!(count_both(50, 1130), count_both(1000, 1130));
+        assert!(!is_valid(1122, 6));
+
+        let mut buffer = [0; 10];
+        assert_eq!(to_digits(402328, &mut buffer), [4, 0, 2, 3, 2, 8]);
+        assert_eq!(to_digits(0, &mut buffer), [0]);
+        assert_eq!(to_digits(u32::MAX, &mut buffer).len(), 10);
+    }
+
+    #[test]
+    fn test_count_valid_sub_ranges() {
+        // a fixed linear congruential sequence, so failures are repeatable
+        let mut seed: u64 = 42;
+        let mut next = || {
+            seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            100000 + (seed >> 33) % 900000
+        };
+
+        for _ in 0..12 {
+            let (a, b) = (next(), next());
+            let (min, max) = (u64::min(a, b), u64::max(a, b));
+            assert_eq!(count_valid(min, max, false), part1_brute(min as u32, max as u32) as u64, "{}..={}", min, max);
+            assert_eq!(count_valid(min, max, true), part2(min as u32, max as u32) as u64, "{}..={}", min, max);
         }
     }
 
-    tr
+    #[test]
+    fn test_count_valid_wide() {
+        // 20 digit passwords are out of reach of the brute force
+        assert!(count_valid(0, u64::MAX, false) > count_valid(0, u64::MAX, true));
+        assert_eq!(count_valid(0, 999999, false) - count_valid(0, 99999, false), part1_brute(100000, 999999) as u64);
+    }
+
+    #[test]
+    fn test_counters() {
+        for (name, counter) in COUNTERS.iter() {
+            assert_eq!(counter(402328, 864247, DupRule::PART1), 454, "{}", name);
+            assert_eq!(counter(402328, 864247, DupRule::PART2), 288, "{}", name);
+            assert_eq!(counter(0, 111, DupRule::PART1), 10, "{}", name);
+            assert_eq!(counter(864247, 402328, DupRule::PART1), 0, "{}", name);
+        }
+    }
+
+    //
+    // Ranges anywhere in the six digit passwords, kept narrow enough for
+    // the brute force to check every number.
+    //
+    fn six_digit_range(max_width: u32) -> impl Strategy<Value = (u32, u32)> {
+        (100000u32..=999999, 0..max_width).prop_map(|(min, width)| (min, u32::min(min + width, 999999)))
+    }
+
+    proptest! {
+        #[test]
+        fn counters_agree((min, max) in six_digit_range(50000)) {
+            for &rule in [DupRule::PART1, DupRule::PART2].iter() {
+                let brute = count_brute(min, max, rule);
+                prop_assert_eq!(count_iterator(min, max, rule), brute, "iterator, {:?}", rule);
+                prop_assert_eq!(count_digits(min, max, rule), brute, "digit counter, {:?}", rule);
+            }
+        }
+
+        #[test]
+        fn iterator_skips_no_password((min, max) in six_digit_range(2000)) {
+            for &rule in [DupRule::PART1, DupRule::PART2].iter() {
+                let expected: Vec<u32> = (min..=max).filter(|&password| is_password(password, rule)).collect();
+                let passwords: Vec<u32> = ValidPasswords::new(min, max, rule).collect();
+                prop_assert_eq!(passwords, expected, "{:?}", rule);
+            }
+        }
+    }
 }
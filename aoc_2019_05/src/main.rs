@@ -1,255 +1,114 @@
 use std::io::{self};
 use std::collections::VecDeque;
+use std::iter::{from_fn, once};
+use intcode::{HaltReason, IntCode};
 
 type Result<T> = ::std::result::Result<T, Box<dyn ::std::error::Error>>;
 
-#[derive(Debug,PartialEq)]
-enum ParameterType {
-    Ref(usize),
-    Value(i32)
+//
+// Runs the program to termination on the given input, returning the memory
+// it finished with and everything it output.
+//
+#[allow(dead_code)]
+fn run(program: &[i64], input_stream: &VecDeque<i64>) -> Result<(Vec<i64>, Vec<i64>)> {
+    let mut machine = IntCode::init(program, input_stream.iter().cloned());
+    machine.run_to_termination()?;
+
+    let outputs = machine.take_outputs();
+    Ok((machine.into_memory(), outputs))
 }
 
-enum Instruction {
-    Add { left_op: ParameterType, right_op: ParameterType, into: ParameterType },
-    Mul { left_op: ParameterType, right_op: ParameterType, into: ParameterType },
-    Input { into: ParameterType },
-    Output { param: ParameterType },
-    JumpIfTrue { cond: ParameterType, to: ParameterType },
-    JumpIfFalse { cond: ParameterType, to: ParameterType },
-    LessThan { left_op: ParameterType, right_op: ParameterType, into: ParameterType },
-    Equals { left_op: ParameterType, right_op: ParameterType, into: ParameterType },
-    Terminate,
-}
-
-struct IntCode {
-    memory: Vec<i32>,
-    address_ptr: usize,
-}
-
-impl IntCode {
-    fn init(memory: &Vec<i32>) -> IntCode {
-        IntCode {
-            memory: memory.clone(),
-            address_ptr: 0
+//
+// Yields each output as soon as the program emits it. An error, including
+// running out of input, ends the stream after it has been yielded.
+//
+fn run_streaming<'a, I>(program: &[i64], input_stream: I) -> impl Iterator<Item = Result<i64>> + 'a where
+    I: Iterator<Item = i64> + 'a
+{
+    let mut machine = IntCode::init(program, input_stream);
+    let mut failed = false;
+
+    from_fn(move || {
+        if failed {
+            return None;
         }
-    }
-
-    fn parse_op_code(input: &i32) -> Result<(u32, VecDeque<ParameterType>)> {
-        let op_code = input % 100;
-        let mut parameter_mode = VecDeque::<ParameterType>::new();
-        let mut parameter_stream = input / 100;
-
-        while parameter_stream > 0 {
-            parameter_mode.push_back(
-                match parameter_stream % 10 {
-                    0 => ParameterType::Ref(0),
-                    1 => ParameterType::Value(0),
-                    _ => { return Err(format!("Invalid OpCode: {}", input).into()) }
-                }
-            );
-            parameter_stream /= 10;
-        }
-
-        Ok((op_code as u32, parameter_mode))
-    }
-
-    fn read_parameter(
-        &mut self,
-        parameter_mode: &mut VecDeque<ParameterType>,
-        is_writing: bool // If parameter is for a write operation, parameter type must be a reference
-    ) -> Result<ParameterType> {
-        let parameter_value = self.memory.get(self.address_ptr).ok_or("Invalid Address, address pointer out of bounds when reading parameter")?;
-        let parameter_type = parameter_mode.pop_front().unwrap_or(ParameterType::Ref(0));
-
-        self.address_ptr = self.address_ptr + 1;
-
-        match parameter_type {
-            ParameterType::Ref(_) => {
-                Ok(ParameterType::Ref(*parameter_value as usize))
-            },
-            ParameterType::Value(_) => {
-                if is_writing {
-                    Err("Invalid parameter type: parameter is for a write operation".into())
-                } else {
-                    Ok(ParameterType::Value(*parameter_value))
-                }
-            }
-        }
-    }
-
-    fn read_instruction(&mut self) -> Result<(Instruction)> {
-        let op_code = self.memory.get(self.address_ptr).ok_or("Invalid Address, address pointer out of bounds when reading instruction")?;
-        self.address_ptr = self.address_ptr + 1;
-
-        let (op_code, mut parameter_mode) = IntCode::parse_op_code(op_code)?;
-
-        let instruction = match op_code {
-            1 => {
-                Instruction::Add {
-                    left_op: self.read_parameter(&mut parameter_mode, false)?,
-                    right_op: self.read_parameter(&mut parameter_mode, false)?,
-                    into: self.read_parameter(&mut parameter_mode, true)?
-                }
-            }
-            2 => {
-                Instruction::Mul {
-                    left_op: self.read_parameter(&mut parameter_mode, false)?,
-                    right_op: self.read_parameter(&mut parameter_mode, false)?,
-                    into: self.read_parameter(&mut parameter_mode, true)?
-                }
-            }
-            3 => {
-                Instruction::Input {
-                    into: self.read_parameter(&mut parameter_mode, true)?
-                }
-            },
-            4 => {
-                Instruction::Output {
-                    param: self.read_parameter(&mut parameter_mode, false)?
-                }
-            }
-            5 => {
-                Instruction::JumpIfTrue {
-                    cond: self.read_parameter(&mut parameter_mode, false)?,
-                    to: self.read_parameter(&mut parameter_mode, false)?
-                }
-            }
-            6 => {
-                Instruction::JumpIfFalse {
-                    cond: self.read_parameter(&mut parameter_mode, false)?,
-                    to: self.read_parameter(&mut parameter_mode, false)?
-                }
-            }
-            7 => {
-                Instruction::LessThan {
-                    left_op: self.read_parameter(&mut parameter_mode, false)?,
-                    right_op: self.read_parameter(&mut parameter_mode, false)?,
-                    into: self.read_parameter(&mut parameter_mode, true)?
-                }
-            },
-            8 => {
-                Instruction::Equals {
-                    left_op: self.read_parameter(&mut parameter_mode, false)?,
-                    right_op: self.read_parameter(&mut parameter_mode, false)?,
-                    into: self.read_parameter(&mut parameter_mode, true)?
-                }
-            }
-            99 => {
-                Instruction::Terminate
-            }
-            _ => {
-                return Err("Invalid Opcode".into());
-            }
-        };
-
-        Ok(instruction)
-    }
 
-    fn resolve_parameter_value(&self, parameter: ParameterType) -> Result<i32> {
-        match parameter {
-            ParameterType::Ref(address) => {
-                Ok(*self.memory.get(address).ok_or(format!("Invalid address reference: {}", address))?)
-            },
-            ParameterType::Value(value) => {
-                Ok(value)
+        match machine.run_until_output_or_input() {
+            Ok(HaltReason::Output) => machine.take_outputs().pop().map(Ok),
+            Ok(HaltReason::NeedInput) => {
+                failed = true;
+                Some(Err("Ran out of input".into()))
             }
-        }
-    }
-
-    fn write_memory(&mut self, into: ParameterType, value: i32) -> Result<()> {
-        match into {
-            ParameterType::Ref(address) => {
-                let into_ref = self.memory.get_mut(address).ok_or(format!("Invalid address reference: {}", address))?;
-                *into_ref = value;
-            },
-            _ => {
-                panic!("")
+            Ok(_) => None,
+            Err(e) => {
+                failed = true;
+                Some(Err(e))
             }
         }
-        Ok(())
-    }
-
-    fn run(&mut self, input_stream: &VecDeque<i32>) -> Result<(&Vec<i32>, Vec<i32>)> {
-        let mut output_stream = Vec::<i32>::new();
-        let mut input_stream = input_stream.clone();
-
-        loop {
-            let instruction = self.read_instruction()?;
-
-            match instruction {
-                Instruction::Add { left_op, right_op, into } => {
-                    let sum = self.resolve_parameter_value(left_op)? + self.resolve_parameter_value(right_op)?;
-                    self.write_memory(into, sum)?;
-                }
-                Instruction::Mul { left_op, right_op, into } => {
-                    let product = self.resolve_parameter_value(left_op)? * self.resolve_parameter_value(right_op)?;
-                    self.write_memory(into, product)?;
-                }
-                Instruction::Input { into } => {
-                    let input_value = input_stream.pop_front().ok_or("Ran out of input")?;
-                    self.write_memory(into, input_value)?;
-                }
-                Instruction::Output { param } => {
-                    output_stream.push(self.resolve_parameter_value(param)?);
-                }
-                Instruction::JumpIfTrue { cond, to } => {
-                    let val = self.resolve_parameter_value(cond)?;
-                    if val != 0 {
-                        self.address_ptr = self.resolve_parameter_value(to)? as usize;
-                    }
-                }
-                Instruction::JumpIfFalse { cond, to } => {
-                    let val = self.resolve_parameter_value(cond)?;
-                    if val == 0 {
-                        self.address_ptr = self.resolve_parameter_value(to)? as usize;
-                    }
-                }
-                Instruction::LessThan { left_op, right_op, into } => {
-                    let less_than = if self.resolve_parameter_value(left_op)? < self.resolve_parameter_value(right_op)? {
-                        1
-                    } else { 0 };
-                    self.write_memory(into, less_than)?;
-                }
-                Instruction::Equals { left_op, right_op, into } => {
-                    let equals = if self.resolve_parameter_value(left_op)? == self.resolve_parameter_value(right_op)? {
-                        1
-                    } else { 0 };
-                    self.write_memory(into, equals)?;
-                }
-                Instruction::Terminate => {
-                    return Ok((&self.memory, output_stream));
-                }
-            };
-        }
-    }
+    })
 }
 
+//
+// Runs the diagnostics for both parts, or with `--system-id N` only for
+// that system.
+//
 fn main() -> Result<()> {
+    let system_id = parse_args(std::env::args().skip(1))?;
+
     let mut input = String::new();
     io::stdin().read_line(&mut input)?;
 
-    let input: Vec<i32> = input
+    let input: Vec<i64> = input
         .split(",")
         .filter_map(|s|
                     s.trim().parse().ok()
         ).collect();
 
-    println!("Part1: {:?}", part1(&input));
-    println!("Part2: {:?}", part2(&input));
+    match system_id {
+        Some(system_id) => println!("{}", run_diagnostic(&input, system_id)?),
+        None => {
+            println!("Part1: {}", run_diagnostic(&input, 1)?);
+            println!("Part2: {}", run_diagnostic(&input, 5)?);
+        }
+    }
 
     Ok(())
 }
 
-fn part1(input: &Vec<i32>) -> Result<Vec<i32>> {
-    let mut mem = IntCode::init(input);
-    let output = mem.run(&VecDeque::from(vec![1]))?;
-    Ok(output.1)
+fn parse_args<I: Iterator<Item = String>>(mut args: I) -> Result<Option<i64>> {
+    let mut system_id = None;
+
+    while let Some(arg) = args.next() {
+        if arg != "--system-id" {
+            return Err(format!("Unknown argument: {}", arg).into());
+        }
+        let id = args.next().ok_or("--system-id needs a value")?;
+        system_id = Some(id.parse().map_err(|e| format!("invalid system id {:?}: {}", id, e))?);
+    }
+
+    Ok(system_id)
 }
 
-fn part2(input: &Vec<i32>) -> Result<Vec<i32>> {
-    let mut mem = IntCode::init(input);
-    let output = mem.run(&VecDeque::from(vec![5]))?;
-    Ok(output.1)
+//
+// Runs the diagnostic program for `system_id` and checks its outputs with
+// `diagnostic_code`.
+//
+fn run_diagnostic(program: &[i64], system_id: i64) -> Result<i64> {
+    let output = run_streaming(program, once(system_id)).collect::<Result<Vec<i64>>>()?;
+    diagnostic_code(&output)
+}
+
+//
+// The answer in a diagnostic program's output. Every output but the last
+// is the result of a test and must be 0; the last is the diagnostic code.
+//
+fn diagnostic_code(output: &[i64]) -> Result<i64> {
+    let (code, tests) = output.split_last().ok_or("program produced no diagnostic code")?;
+
+    match tests.iter().position(|result| *result != 0) {
+        Some(index) => Err(format!("diagnostic test {} failed with {}", index, tests[index]).into()),
+        None => Ok(*code)
+    }
 }
 
 #[cfg(test)]
@@ -258,94 +117,143 @@ mod test {
 
     #[test]
     fn test_basic() {
-        let mut mem = IntCode::init(&vec![1,9,10,3,2,3,11,0,99,30,40,50]);
-        assert_eq!(*mem.run(&VecDeque::new()).unwrap().0, vec![3500,9,10,70,2,3,11,0,99,30,40,50]);
-
-        let mut mem = IntCode::init(&vec![1,0,0,0,99]);
-        assert_eq!(*mem.run(&VecDeque::new()).unwrap().0, vec![2,0,0,0,99]);
-
-        let mut mem = IntCode::init(&vec![2,3,0,3,99]);
-        assert_eq!(*mem.run(&VecDeque::new()).unwrap().0, vec![2,3,0,6,99]);
-
-        let mut mem = IntCode::init(&vec![2,4,4,5,99,0]);
-        assert_eq!(*mem.run(&VecDeque::new()).unwrap().0, vec![2,4,4,5,99,9801]);
-
-        let mut mem = IntCode::init(&vec![1,1,1,4,99,5,6,0,99]);
-        assert_eq!(*mem.run(&VecDeque::new()).unwrap().0, vec![30,1,1,4,2,5,6,0,99]);
+        assert_eq!(run(&[1,9,10,3,2,3,11,0,99,30,40,50], &VecDeque::new()).unwrap().0, vec![3500,9,10,70,2,3,11,0,99,30,40,50]);
+        assert_eq!(run(&[1,0,0,0,99], &VecDeque::new()).unwrap().0, vec![2,0,0,0,99]);
+        assert_eq!(run(&[2,3,0,3,99], &VecDeque::new()).unwrap().0, vec![2,3,0,6,99]);
+        assert_eq!(run(&[2,4,4,5,99,0], &VecDeque::new()).unwrap().0, vec![2,4,4,5,99,9801]);
+        assert_eq!(run(&[1,1,1,4,99,5,6,0,99], &VecDeque::new()).unwrap().0, vec![30,1,1,4,2,5,6,0,99]);
     }
 
     #[test]
     fn test_inout() {
-        let mut mem = IntCode::init(&vec![3,0,4,0,3,1,4,1,99]);
-        let run = mem.run(&VecDeque::from(vec![42, 58])).unwrap();
-        assert_eq!(run.1, vec![42, 58]);
+        let (_, outputs) = run(&[3,0,4,0,3,1,4,1,99], &VecDeque::from(vec![42, 58])).unwrap();
+        assert_eq!(outputs, vec![42, 58]);
     }
 
     #[test]
     fn test_is_equal_to_8_position() {
-        let mut mem = IntCode::init(&vec![3,9,8,9,10,9,4,9,99,-1,8]);
-        let run = mem.run(&VecDeque::from(vec![8])).unwrap();
-        assert_eq!(run.1, vec![1]);
+        let (_, outputs) = run(&[3,9,8,9,10,9,4,9,99,-1,8], &VecDeque::from(vec![8])).unwrap();
+        assert_eq!(outputs, vec![1]);
 
-        let mut mem = IntCode::init(&vec![3,9,8,9,10,9,4,9,99,-1,8]);
-        let run = mem.run(&VecDeque::from(vec![7])).unwrap();
-        assert_eq!(run.1, vec![0]);
+        let (_, outputs) = run(&[3,9,8,9,10,9,4,9,99,-1,8], &VecDeque::from(vec![7])).unwrap();
+        assert_eq!(outputs, vec![0]);
     }
 
     #[test]
     fn test_less_than_8_position() {
-        let mut mem = IntCode::init(&vec![3,9,7,9,10,9,4,9,99,-1,8]);
-        let run = mem.run(&VecDeque::from(vec![8])).unwrap();
-        assert_eq!(run.1, vec![0]);
+        let (_, outputs) = run(&[3,9,7,9,10,9,4,9,99,-1,8], &VecDeque::from(vec![8])).unwrap();
+        assert_eq!(outputs, vec![0]);
 
-        let mut mem = IntCode::init(&vec![3,9,7,9,10,9,4,9,99,-1,8]);
-        let run = mem.run(&VecDeque::from(vec![7])).unwrap();
-        assert_eq!(run.1, vec![1]);
+        let (_, outputs) = run(&[3,9,7,9,10,9,4,9,99,-1,8], &VecDeque::from(vec![7])).unwrap();
+        assert_eq!(outputs, vec![1]);
 
-        let mut mem = IntCode::init(&vec![3,9,7,9,10,9,4,9,99,-1,8]);
-        let run = mem.run(&VecDeque::from(vec![42])).unwrap();
-        assert_eq!(run.1, vec![0]);
+        let (_, outputs) = run(&[3,9,7,9,10,9,4,9,99,-1,8], &VecDeque::from(vec![42])).unwrap();
+        assert_eq!(outputs, vec![0]);
     }
 
     #[test]
     fn test_is_equal_to_8_immediate() {
-        let mut mem = IntCode::init(&vec![3,3,1108,-1,8,3,4,3,99]);
-        let run = mem.run(&VecDeque::from(vec![8])).unwrap();
-        assert_eq!(run.1, vec![1]);
+        let (_, outputs) = run(&[3,3,1108,-1,8,3,4,3,99], &VecDeque::from(vec![8])).unwrap();
+        assert_eq!(outputs, vec![1]);
 
-        let mut mem = IntCode::init(&vec![3,3,1108,-1,8,3,4,3,99]);
-        let run = mem.run(&VecDeque::from(vec![7])).unwrap();
-        assert_eq!(run.1, vec![0]);
+        let (_, outputs) = run(&[3,3,1108,-1,8,3,4,3,99], &VecDeque::from(vec![7])).unwrap();
+        assert_eq!(outputs, vec![0]);
     }
 
     #[test]
     fn test_is_less_than_8_immediate() {
-        let mut mem = IntCode::init(&vec![3,3,1107,-1,8,3,4,3,99]);
-        let run = mem.run(&VecDeque::from(vec![8])).unwrap();
-        assert_eq!(run.1, vec![0]);
+        let (_, outputs) = run(&[3,3,1107,-1,8,3,4,3,99], &VecDeque::from(vec![8])).unwrap();
+        assert_eq!(outputs, vec![0]);
 
-        let mut mem = IntCode::init(&vec![3,3,1107,-1,8,3,4,3,99]);
-        let run = mem.run(&VecDeque::from(vec![42])).unwrap();
-        assert_eq!(run.1, vec![0]);
+        let (_, outputs) = run(&[3,3,1107,-1,8,3,4,3,99], &VecDeque::from(vec![42])).unwrap();
+        assert_eq!(outputs, vec![0]);
 
-        let mut mem = IntCode::init(&vec![3,3,1107,-1,8,3,4,3,99]);
-        let run = mem.run(&VecDeque::from(vec![-3])).unwrap();
-        assert_eq!(run.1, vec![1]);
+        let (_, outputs) = run(&[3,3,1107,-1,8,3,4,3,99], &VecDeque::from(vec![-3])).unwrap();
+        assert_eq!(outputs, vec![1]);
     }
 
     #[test]
     fn test_day5_complex() {
-        let mut mem = IntCode::init(&vec![3,21,1008,21,8,20,1005,20,22,107,8,21,20,1006,20,31,1106,0,36,98,0,0,1002,21,125,20,4,20,1105,1,46,104,999,1105,1,46,1101,1000,1,20,4,20,1105,1,46,98,99]);
-        let run = mem.run(&VecDeque::from(vec![-42])).unwrap();
-        assert_eq!(run.1, vec![999]);
+        let (_, outputs) = run(&[3,21,1008,21,8,20,1005,20,22,107,8,21,20,1006,20,31,1106,0,36,98,0,0,1002,21,125,20,4,20,1105,1,46,104,999,1105,1,46,1101,1000,1,20,4,20,1105,1,46,98,99], &VecDeque::from(vec![-42])).unwrap();
+        assert_eq!(outputs, vec![999]);
+
+        let (_, outputs) = run(&[3,21,1008,21,8,20,1005,20,22,107,8,21,20,1006,20,31,1106,0,36,98,0,0,1002,21,125,20,4,20,1105,1,46,104,999,1105,1,46,1101,1000,1,20,4,20,1105,1,46,98,99], &VecDeque::from(vec![8])).unwrap();
+        assert_eq!(outputs, vec![1000]);
 
-        let mut mem = IntCode::init(&vec![3,21,1008,21,8,20,1005,20,22,107,8,21,20,1006,20,31,1106,0,36,98,0,0,1002,21,125,20,4,20,1105,1,46,104,999,1105,1,46,1101,1000,1,20,4,20,1105,1,46,98,99]);
-        let run = mem.run(&VecDeque::from(vec![8])).unwrap();
-        assert_eq!(run.1, vec![1000]);
+        let (_, outputs) = run(&[3,21,1008,21,8,20,1005,20,22,107,8,21,20,1006,20,31,1106,0,36,98,0,0,1002,21,125,20,4,20,1105,1,46,104,999,1105,1,46,1101,1000,1,20,4,20,1105,1,46,98,99], &VecDeque::from(vec![42])).unwrap();
+        assert_eq!(outputs, vec![1001]);
+    }
 
-        let mut mem = IntCode::init(&vec![3,21,1008,21,8,20,1005,20,22,107,8,21,20,1006,20,31,1106,0,36,98,0,0,1002,21,125,20,4,20,1105,1,46,104,999,1105,1,46,1101,1000,1,20,4,20,1105,1,46,98,99]);
-        let run = mem.run(&VecDeque::from(vec![42])).unwrap();
-        assert_eq!(run.1, vec![1001]);
+    #[test]
+    fn test_relative_mode() {
+        // the day 9 quine
+        let quine = [109,1,204,-1,1001,100,1,100,1008,100,16,101,1006,101,0,99];
+        let (_, outputs) = run(&quine, &VecDeque::new()).unwrap();
+        assert_eq!(outputs, quine);
+
+        // 0: RB #5; 2: IN -> [rb+2]; 4: OUT [7]; 6: HALT
+        let (memory, outputs) = run(&[109,5,203,2,4,7,99,0], &VecDeque::from(vec![42])).unwrap();
+        assert_eq!(outputs, vec![42]);
+        assert_eq!(memory, vec![109,5,203,2,4,7,99,42]);
     }
 
+    #[test]
+    fn test_run_streaming() {
+        let program = vec![3,21,1008,21,8,20,1005,20,22,107,8,21,20,1006,20,31,1106,0,36,98,0,0,1002,21,125,20,4,20,1105,1,46,104,999,1105,1,46,1101,1000,1,20,4,20,1105,1,46,98,99];
+
+        let output: Vec<i64> = run_streaming(&program, once(8)).map(|x| x.unwrap()).collect();
+        assert_eq!(output, vec![1000]);
+
+        let mut stream = run_streaming(&[3,0,4,0,3,1,4,1,99], vec![42, 58].into_iter());
+        assert_eq!(stream.next().unwrap().unwrap(), 42);
+        assert_eq!(stream.next().unwrap().unwrap(), 58);
+        assert!(stream.next().is_none());
+    }
+
+    #[test]
+    fn test_run_streaming_error() {
+        let mut stream = run_streaming(&[4,0,3,0,99], ::std::iter::empty());
+        assert_eq!(stream.next().unwrap().unwrap(), 4);
+        assert!(stream.next().unwrap().is_err());
+        assert!(stream.next().is_none());
+    }
+
+    #[test]
+    fn test_run_diagnostic() {
+        // outputs 0, 0, then the system id
+        assert_eq!(run_diagnostic(&[3,0,104,0,104,0,4,0,99], 5).unwrap(), 5);
+        assert_eq!(run_diagnostic(&[3,0,4,0,99], 1).unwrap(), 1);
+
+        let error = |program: &[i64]| run_diagnostic(program, 1).unwrap_err().to_string();
+        assert_eq!(error(&[104,0,104,3,104,0,104,42,99]), "diagnostic test 1 failed with 3");
+        assert_eq!(error(&[99]), "program produced no diagnostic code");
+    }
+
+    #[test]
+    fn test_diagnostic_code() {
+        assert_eq!(diagnostic_code(&[0, 0, 0, 15386262]).unwrap(), 15386262);
+        assert_eq!(diagnostic_code(&[7]).unwrap(), 7);
+        assert_eq!(diagnostic_code(&[0, 0, 0]).unwrap(), 0);
+
+        // the first failing test is reported, not the last
+        assert_eq!(diagnostic_code(&[0, 4, 0, 9, 42]).unwrap_err().to_string(), "diagnostic test 1 failed with 4");
+        assert_eq!(diagnostic_code(&[]).unwrap_err().to_string(), "program produced no diagnostic code");
+    }
+
+    #[test]
+    fn test_system_ids() {
+        // outputs 999, 1000 or 1001 for input below, equal to or above 8
+        let program = [3,21,1008,21,8,20,1005,20,22,107,8,21,20,1006,20,31,1106,0,36,98,0,0,1002,21,125,20,4,20,1105,1,46,104,999,1105,1,46,1101,1000,1,20,4,20,1105,1,46,98,99];
+        assert_eq!(run_diagnostic(&program, 7).unwrap(), 999);
+        assert_eq!(run_diagnostic(&program, 8).unwrap(), 1000);
+        assert_eq!(run_diagnostic(&program, 9).unwrap(), 1001);
+
+        let args = |args: &[&str]| parse_args(args.iter().map(|arg| arg.to_string()));
+        assert_eq!(args(&[]).unwrap(), None);
+        assert_eq!(args(&["--system-id", "8"]).unwrap(), Some(8));
+        assert_eq!(args(&["--system-id", "-3"]).unwrap(), Some(-3));
+        assert_eq!(args(&["--system-id", "eight"]).unwrap_err().to_string(), "invalid system id \"eight\": invalid digit found in string");
+        assert_eq!(args(&["--system-id"]).unwrap_err().to_string(), "--system-id needs a value");
+        assert_eq!(args(&["8"]).unwrap_err().to_string(), "Unknown argument: 8");
+    }
 }
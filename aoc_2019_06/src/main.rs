@@ -1,108 +1,396 @@
+use std::env;
+use std::fs;
 use std::io::{self, Read};
 use std::collections::HashMap;
 use std::collections::HashSet;
-use std::collections::VecDeque;
+use aoc_util::search::bfs;
 
 type Result<T> = ::std::result::Result<T, Box<dyn ::std::error::Error>>;
 
-type AdjList = HashMap<String, Vec<String>>;
+type AdjList = Vec<Vec<u32>>;
 
-fn add_adj(graph: &mut AdjList, from: &str, to: &str) {
-    let key: String = from.to_string();
-    if !graph.contains_key(&key) {
-        graph.insert(from.to_string(), Vec::new());
+//
+// Body names, interned so the graph can refer to bodies by id: their
+// index in order of first appearance.
+//
+#[derive(Debug, Default)]
+struct Names {
+    names: Vec<String>,
+    ids: HashMap<String, u32>
+}
+
+impl Names {
+    fn intern(&mut self, name: &str) -> u32 {
+        if let Some(&id) = self.ids.get(name) {
+            return id;
+        }
+
+        let id = self.names.len() as u32;
+        self.names.push(name.to_string());
+        self.ids.insert(name.to_string(), id);
+        id
+    }
+
+    fn id(&self, name: &str) -> Option<u32> {
+        self.ids.get(name).copied()
+    }
+
+    fn name(&self, id: u32) -> &str {
+        &self.names[id as usize]
     }
-    graph.get_mut(&key).unwrap().push(to.to_string());
 }
 
-fn parse_input(input: &String) -> Result<AdjList> {
-    let mut graph = AdjList::new();
+//
+// The orbit map: the bodies next to each body, in either direction, and
+// the one each body orbits, all by id.
+//
+#[derive(Debug, Default)]
+struct Orbits {
+    graph: AdjList,
+    parents: Vec<Option<u32>>,
+    names: Names
+}
 
-    input.lines()
-        .for_each(|x| {
-            let v: Vec<&str> = x.split(')').collect();
-            assert_eq!(v.len(), 2);
-            add_adj(&mut graph, v[0], v[1]);
-            add_adj(&mut graph, v[1], v[0]);
-        });
+impl Orbits {
+    fn add_body(&mut self, name: &str) -> u32 {
+        let id = self.names.intern(name);
+        if id as usize == self.graph.len() {
+            self.graph.push(Vec::new());
+            self.parents.push(None);
+        }
+        id
+    }
 
-    Ok(graph)
+    fn parent(&self, id: u32) -> Option<u32> {
+        self.parents[id as usize]
+    }
 }
 
-fn dfs(graph: &AdjList, curr: &String, prev: &String, curr_cnt: u32) -> u32 {
-    let mut tr = curr_cnt;
+//
+// Reads one `A)B` orbit per line, skipping blank lines. Errors about a
+// single line give its 1-based number; those about the map as a whole,
+// from check_orbits, can't point at one line.
+//
+fn parse_input(input: &str) -> Result<Orbits> {
+    let mut orbits = Orbits::default();
+
+    for (index, x) in input.lines().map(|x| x.trim()).enumerate().filter(|(_, x)| !x.is_empty()) {
+        let line = index + 1;
+        let v: Vec<&str> = x.split(')').collect();
+        if v.len() != 2 || v.iter().any(|name| name.is_empty()) {
+            return Err(format!("line {}: Invalid orbit: {}", line, x).into());
+        }
 
-    if let Some(adj) = graph.get(curr) {
-        for u in adj {
-            if u != prev {
-                tr = tr + dfs(graph, u, curr, curr_cnt + 1);
+        let parent = orbits.add_body(v[0]);
+        let body = orbits.add_body(v[1]);
+        if let Some(previous) = orbits.parents[body as usize].replace(parent) {
+            if previous == parent {
+                return Err(format!("line {}: Duplicate orbit: {}", line, x).into());
             }
+            return Err(format!("line {}: {} orbits both {} and {}", line, v[1], orbits.names.name(previous), v[0]).into());
+        }
+        orbits.graph[parent as usize].push(body);
+        orbits.graph[body as usize].push(parent);
+    }
+
+    check_orbits(&orbits)?;
+    Ok(orbits)
+}
+
+//
+// With every body orbiting at most one other, the orbits form a tree
+// rooted at COM as long as COM orbits nothing, every other body orbits
+// something, and following orbits inwards never comes back around.
+//
+fn check_orbits(orbits: &Orbits) -> Result<()> {
+    let name = |id| orbits.names.name(id);
+
+    if let Some(parent) = orbits.names.id("COM").and_then(|com| orbits.parent(com)) {
+        return Err(format!("COM orbits {}", name(parent)).into());
+    }
+
+    // in order of appearance, so the same map always reports the same body
+    let mut checked = vec![false; orbits.graph.len()];
+    for body in 0..orbits.graph.len() as u32 {
+        let mut chain = HashSet::new();
+        let mut current = body;
+
+        while !checked[current as usize] {
+            if !chain.insert(current) {
+                return Err(format!("Orbit cycle through {}", name(current)).into());
+            }
+            current = match orbits.parent(current) {
+                Some(parent) => parent,
+                None if name(current) == "COM" => break,
+                None => return Err(format!("{} orbits nothing", name(current)).into())
+            };
+        }
+
+        for id in chain {
+            checked[id as usize] = true;
+        }
+    }
+
+    Ok(())
+}
+
+//
+// The original recursive count, one call per orbit deep; kept to check
+// part 1 against on small maps.
+//
+#[allow(dead_code)]
+fn dfs(graph: &AdjList, curr: u32, prev: Option<u32>, curr_cnt: u32) -> u32 {
+    let mut tr = curr_cnt;
+
+    for &u in &graph[curr as usize] {
+        if Some(u) != prev {
+            tr = tr + dfs(graph, u, Some(curr), curr_cnt + 1);
+        }
+    }
+    tr
+}
+
+//
+// Every body orbits everything between it and COM, so the total is the
+// sum of their depths. Found breadth first, as a long chain of orbits
+// would overflow the stack of a recursive walk.
+//
+fn part1(orbits: &Orbits) -> Result<u64> {
+    let com = orbits.names.id("COM").ok_or("COM is not in the orbit map")?;
+    Ok(depths(&orbits.graph, com).values().map(|depth| *depth as u64).sum())
+}
+
+fn depths(graph: &AdjList, root: u32) -> HashMap<u32, u32> {
+    bfs(root, |node: &u32| graph[*node as usize].iter().copied())
+}
+
+//
+// Number of orbits between every body and COM.
+//
+fn orbit_depths(orbits: &Orbits) -> HashMap<String, u32> {
+    let com = match orbits.names.id("COM") {
+        Some(com) => com,
+        None => return HashMap::new()
+    };
+
+    depths(&orbits.graph, com)
+        .into_iter()
+        .map(|(id, depth)| (orbits.names.name(id).to_string(), depth))
+        .collect()
+}
+
+//
+// Path from a body back to COM, starting with the body itself.
+//
+fn ancestors(orbits: &Orbits, id: u32) -> Vec<u32> {
+    let mut chain = vec![id];
+    while let Some(parent) = orbits.parent(*chain.last().unwrap()) {
+        chain.push(parent);
+    }
+    chain
+}
+
+fn lowest_common_ancestor(orbits: &Orbits, a: u32, b: u32) -> Option<u32> {
+    let b_chain: HashSet<u32> = ancestors(orbits, b).into_iter().collect();
+    ancestors(orbits, a).into_iter().find(|id| b_chain.contains(id))
+}
+
+//
+// Bodies passed through moving from the body `a` orbits to the body `b`
+// orbits, both included: in towards their common ancestor, then out.
+//
+fn path_between(orbits: &Orbits, a: &str, b: &str) -> Option<Vec<String>> {
+    let from = orbits.parent(orbits.names.id(a)?)?;
+    let to = orbits.parent(orbits.names.id(b)?)?;
+    let lca = lowest_common_ancestor(orbits, from, to)?;
+
+    let mut path: Vec<u32> = ancestors(orbits, from).into_iter().take_while(|id| *id != lca).collect();
+    let outwards: Vec<u32> = ancestors(orbits, to).into_iter().take_while(|id| *id != lca).collect();
+    path.push(lca);
+    path.extend(outwards.into_iter().rev());
+    Some(path.into_iter().map(|id| orbits.names.name(id).to_string()).collect())
+}
+
+//
+// Orbital transfers needed to move from the body `a` orbits to the body
+// `b` orbits: in to their common ancestor and back out, with depths
+// counted along the parent pointers rather than by searching the map.
+//
+fn transfers_between(orbits: &Orbits, a: &str, b: &str) -> Option<u32> {
+    let from = orbits.parent(orbits.names.id(a)?)?;
+    let to = orbits.parent(orbits.names.id(b)?)?;
+    let lca = lowest_common_ancestor(orbits, from, to)?;
+
+    let depth = |id| ancestors(orbits, id).len() as u32 - 1;
+    Some(depth(from) + depth(to) - 2 * depth(lca))
+}
+
+fn check_you_and_san(orbits: &Orbits) -> Result<()> {
+    for body in ["YOU", "SAN"].iter() {
+        if orbits.names.id(body).is_none() {
+            return Err(format!("{} is not in the orbit map", body).into());
         }
-        tr
-    } else {
-        curr_cnt
     }
+    Ok(())
 }
 
-fn part1(graph: &AdjList) -> u32 {
-    dfs(graph, &"COM".to_string(), &"".to_string(), 0)
+fn transfer_path(orbits: &Orbits) -> Result<Vec<String>> {
+    check_you_and_san(orbits)?;
+    path_between(orbits, "YOU", "SAN").ok_or_else(|| "Couldn't find a path from YOU to SAN".into())
 }
 
-struct QueueElement {
-    node: String,
-    dist: u32
+fn part2(orbits: &Orbits) -> Result<u32> {
+    check_you_and_san(orbits)?;
+    transfers_between(orbits, "YOU", "SAN").ok_or_else(|| "Couldn't find a path from YOU to SAN".into())
 }
 
-fn part2(graph: &AdjList) -> Result<u32> {
-    let mut queue = VecDeque::<QueueElement>::new();
-    let mut visited = HashSet::<String>::new();
+//
+// Quoted DOT id for a body. Names are always quoted, so only quotes and
+// backslashes need escaping.
+//
+fn dot_id(name: &str) -> String {
+    format!("\"{}\"", name.replace('\\', "\\\\").replace('"', "\\\""))
+}
 
-    queue.push_back(QueueElement {
-        node: "YOU".to_string(),
-        dist: 0
-    });
-    visited.insert("YOU".to_string());
+//
+// The orbit map as a Graphviz digraph, with an edge from each body to
+// the ones orbiting it. COM, YOU and SAN get their own colours, and the
+// edges between consecutive bodies of `route` are drawn bold, whichever
+// way round they go.
+//
+fn to_dot(orbits: &Orbits, route: &[String]) -> String {
+    let mut on_route = HashSet::new();
+    for pair in route.windows(2) {
+        on_route.insert((pair[0].as_str(), pair[1].as_str()));
+        on_route.insert((pair[1].as_str(), pair[0].as_str()));
+    }
 
-    while !queue.is_empty() {
-        let top = queue.pop_front().unwrap();
+    let mut dot = String::from("digraph orbits {\n");
+    for (body, colour) in [("COM", "gold"), ("YOU", "lightblue"), ("SAN", "salmon")].iter() {
+        if orbits.names.id(body).is_some() {
+            dot += &format!("    {} [style=filled, fillcolor={}];\n", dot_id(body), colour);
+        }
+    }
 
-        if top.node == "SAN" {
-            return Ok(top.dist - 2);
+    for body in 0..orbits.graph.len() as u32 {
+        if let Some(parent) = orbits.parent(body) {
+            let (parent, body) = (orbits.names.name(parent), orbits.names.name(body));
+            let style = if on_route.contains(&(parent, body)) { " [style=bold, color=red]" } else { "" };
+            dot += &format!("    {} -> {}{};\n", dot_id(parent), dot_id(body), style);
         }
+    }
 
-        for u in graph.get(&top.node).unwrap() {
-            if !visited.contains(u) {
-                visited.insert(u.to_string());
-                queue.push_back(QueueElement {
-                    node: u.to_string(),
-                    dist: top.dist + 1
-                });
-            }
+    dot + "}\n"
+}
+
+//
+// Reads the orbit map from the file given as an argument, or from stdin
+// if there is none. `--depths` also lists every body with the number of
+// orbits between it and COM, shallowest first, `--path` the bodies
+// passed through on the way from YOU to SAN, and `--dot FILE` writes the
+// map to FILE as a Graphviz graph with that route picked out.
+//
+//
+// What to print besides the answers, and where to read the map from when
+// not stdin.
+//
+#[derive(Debug, Default, PartialEq)]
+struct Options {
+    path: Option<String>,
+    show_depths: bool,
+    show_path: bool,
+    dot_file: Option<String>
+}
+
+//
+// Reads `--depths`, `--path`, `--dot FILE` and an input file name. Any
+// other flag is an error rather than a file that doesn't exist.
+//
+fn parse_args<I: Iterator<Item = String>>(mut args: I) -> Result<Options> {
+    let mut options = Options::default();
+
+    while let Some(arg) = args.next() {
+        if arg == "--depths" {
+            options.show_depths = true;
+        } else if arg == "--path" {
+            options.show_path = true;
+        } else if arg == "--dot" {
+            options.dot_file = Some(args.next().ok_or("--dot needs a file name")?);
+        } else if arg.starts_with("--") {
+            return Err(format!("Unknown argument: {}", arg).into());
+        } else {
+            options.path = Some(arg);
         }
     }
 
-    Err("Couldn't find a path from YOU to SAN".into())
+    Ok(options)
 }
 
 fn main() -> Result<()>{
-    let mut input = String::new();
-    io::stdin().read_to_string(&mut input)?;
+    let Options { path, show_depths, show_path, dot_file } = parse_args(env::args().skip(1))?;
+
+    let input = match path {
+        Some(path) => fs::read_to_string(path)?,
+        None => {
+            let mut input = String::new();
+            io::stdin().read_to_string(&mut input)?;
+            input
+        }
+    };
 
     let graph = parse_input(&input)?;
 
-    println!("part1: {}", part1(&graph));
+    println!("part1: {}", part1(&graph)?);
     println!("part2: {}", part2(&graph)?);
 
+    if show_path {
+        println!("{}", transfer_path(&graph)?.join(" -> "));
+    }
+
+    if let Some(dot_file) = dot_file {
+        let mut route = vec!["YOU".to_string()];
+        route.extend(transfer_path(&graph)?);
+        route.push("SAN".to_string());
+        fs::write(dot_file, to_dot(&graph, &route))?;
+    }
+
+    if show_depths {
+        let mut depths: Vec<(String, u32)> = orbit_depths(&graph).into_iter().collect();
+        depths.sort_by(|a, b| (a.1, &a.0).cmp(&(b.1, &b.0)));
+        for (body, depth) in depths {
+            println!("{} {}", body, depth);
+        }
+    }
+
     Ok(())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::time::Instant;
+
+    fn common_ancestor(orbits: &Orbits, a: &str, b: &str) -> Option<String> {
+        let lca = lowest_common_ancestor(orbits, orbits.names.id(a)?, orbits.names.id(b)?)?;
+        Some(orbits.names.name(lca).to_string())
+    }
+
+    #[test]
+    fn test_parse_args() {
+        let args = |args: &[&str]| parse_args(args.iter().map(|arg| arg.to_string()));
+
+        assert_eq!(args(&[]).unwrap(), Options::default());
+        assert_eq!(args(&["--path", "map.txt", "--dot", "map.dot"]).unwrap(),
+                   Options { path: Some("map.txt".to_string()), show_path: true, dot_file: Some("map.dot".to_string()), ..Options::default() });
+        assert!(args(&["--depths"]).unwrap().show_depths);
+
+        assert_eq!(args(&["--depth"]).unwrap_err().to_string(), "Unknown argument: --depth");
+        assert_eq!(args(&["--dot"]).unwrap_err().to_string(), "--dot needs a file name");
+    }
 
     #[test]
     fn test_part1() {
-        let graph = parse_input(&"COM)B
+        let graph = parse_input("COM)B
 B)C
 C)D
 D)E
@@ -112,13 +400,155 @@ G)H
 D)I
 E)J
 J)K
-K)L".to_string()).unwrap();
-        assert_eq!(part1(&graph), 42);
+K)L").unwrap();
+        assert_eq!(part1(&graph).unwrap(), 42);
+    }
+
+    const SAMPLE_PART2: &str = "COM)B
+B)C
+C)D
+D)E
+E)F
+B)G
+G)H
+D)I
+E)J
+J)K
+K)L
+K)YOU
+I)SAN";
+
+    #[test]
+    fn test_common_ancestor() {
+        let graph = parse_input(SAMPLE_PART2).unwrap();
+        assert_eq!(common_ancestor(&graph, "YOU", "SAN"), Some("D".to_string()));
+        assert_eq!(transfers_between(&graph, "YOU", "SAN"), Some(4));
+
+        assert_eq!(common_ancestor(&graph, "COM", "L"), Some("COM".to_string()));
+        assert_eq!(common_ancestor(&graph, "B", "COM"), Some("COM".to_string()));
+        assert_eq!(transfers_between(&graph, "B", "H"), Some(2));
+        assert_eq!(transfers_between(&graph, "COM", "H"), None);
+        assert_eq!(common_ancestor(&graph, "YOU", "nowhere"), None);
+    }
+
+    #[test]
+    fn test_part1_matches_dfs() {
+        let graph = parse_input(SAMPLE_PART2).unwrap();
+        let com = graph.names.id("COM").unwrap();
+        assert_eq!(part1(&graph).unwrap(), dfs(&graph.graph, com, None, 0) as u64);
+        assert_eq!(part1(&graph).unwrap(), 54);
+    }
+
+    #[test]
+    fn test_orbit_depths() {
+        let graph = parse_input(SAMPLE_PART2).unwrap();
+        let depths = orbit_depths(&graph);
+        assert_eq!(depths["COM"], 0);
+        assert_eq!(depths["D"], 3);
+        assert_eq!(depths["L"], 7);
+        assert_eq!(depths["YOU"], 7);
+        assert_eq!(depths["SAN"], 5);
+        assert_eq!(depths.len(), 14);
+    }
+
+    #[test]
+    fn test_part1_deep_chain() {
+        let n: u64 = 200_000;
+        let mut input = String::from("COM)0\n");
+        for i in 1..n {
+            input += &format!("{}){}\n", i - 1, i);
+        }
+
+        let graph = parse_input(&input).unwrap();
+        assert_eq!(part1(&graph).unwrap(), n * (n + 1) / 2);
+    }
+
+    #[test]
+    fn test_invalid_orbits() {
+        let error = |input| parse_input(input).unwrap_err().to_string();
+        assert_eq!(error("COM)A\nA)B\nA)B"), "line 3: Duplicate orbit: A)B");
+        assert_eq!(error("COM)A\nA)B\nCOM)B"), "line 3: B orbits both A and COM");
+        assert_eq!(error("COM)C\nA)B\nB)A"), "Orbit cycle through A");
+        assert_eq!(error("COM)A\nA)B\nB)COM"), "COM orbits B");
+        assert_eq!(error("COM)A\nX)B"), "X orbits nothing");
+        assert_eq!(error("COM)A\nA-B"), "line 2: Invalid orbit: A-B");
+
+        assert_eq!(error("A)B\nbroken"), "line 2: Invalid orbit: broken");
+        assert_eq!(error("A)B)C"), "line 1: Invalid orbit: A)B)C");
+        assert_eq!(error("COM)A\nA)"), "line 2: Invalid orbit: A)");
+        // blank lines still count
+        assert_eq!(error("COM)A\n\n  \n)B"), "line 4: Invalid orbit: )B");
+    }
+
+    #[test]
+    fn test_transfer_path() {
+        let graph = parse_input(SAMPLE_PART2).unwrap();
+        assert_eq!(transfer_path(&graph).unwrap(), ["K", "J", "E", "D", "I"]);
+        assert_eq!(transfer_path(&graph).unwrap().len() as u32 - 1, part2(&graph).unwrap());
+
+        // straight out from COM, and back in
+        assert_eq!(path_between(&graph, "B", "H").unwrap(), ["COM", "B", "G"]);
+        assert_eq!(path_between(&graph, "L", "C").unwrap(), ["K", "J", "E", "D", "C", "B"]);
+    }
+
+    #[test]
+    fn test_to_dot() {
+        let graph = parse_input(SAMPLE_PART2).unwrap();
+        let mut route = vec!["YOU".to_string()];
+        route.extend(transfer_path(&graph).unwrap());
+        route.push("SAN".to_string());
+        let dot = to_dot(&graph, &route);
+        let lines: Vec<&str> = dot.lines().collect();
+
+        assert_eq!(lines[0], "digraph orbits {");
+        assert_eq!(*lines.last().unwrap(), "}");
+        assert!(lines.contains(&"    \"COM\" [style=filled, fillcolor=gold];"));
+        assert!(lines.contains(&"    \"YOU\" [style=filled, fillcolor=lightblue];"));
+        assert!(lines.contains(&"    \"SAN\" [style=filled, fillcolor=salmon];"));
+
+        // every hop from YOU in to D and back out to SAN is bold
+        for edge in ["\"K\" -> \"YOU\"", "\"J\" -> \"K\"", "\"E\" -> \"J\"", "\"D\" -> \"E\"",
+                     "\"D\" -> \"I\"", "\"I\" -> \"SAN\""].iter() {
+            assert!(lines.contains(&format!("    {} [style=bold, color=red];", edge).as_str()), "{}", edge);
+        }
+        assert!(lines.contains(&"    \"COM\" -> \"B\";"));
+        assert!(lines.contains(&"    \"K\" -> \"L\";"));
+        assert_eq!(lines.iter().filter(|line| line.contains("->")).count(), 13);
+    }
+
+    #[test]
+    fn test_dot_id() {
+        assert_eq!(dot_id("Q7x"), "\"Q7x\"");
+        assert_eq!(dot_id("1A"), "\"1A\"");
+        assert_eq!(dot_id("a\"b\\c"), "\"a\\\"b\\\\c\"");
+    }
+
+    #[test]
+    fn test_missing_bodies() {
+        let graph = parse_input("").unwrap();
+        assert_eq!(part1(&graph).unwrap_err().to_string(), "COM is not in the orbit map");
+
+        let graph = parse_input("COM)B\nB)SAN").unwrap();
+        assert_eq!(part1(&graph).unwrap(), 3);
+        assert_eq!(part2(&graph).unwrap_err().to_string(), "YOU is not in the orbit map");
+
+        let graph = parse_input("COM)B\nB)YOU").unwrap();
+        assert_eq!(part2(&graph).unwrap_err().to_string(), "SAN is not in the orbit map");
+
+        // both orbit COM, so no transfers are needed
+        let graph = parse_input("COM)YOU\nCOM)SAN").unwrap();
+        assert_eq!(part2(&graph).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_blank_lines() {
+        let graph = parse_input("COM)B\n\nB)C\n  \nC)D\n").unwrap();
+        assert_eq!(part1(&graph).unwrap(), 6);
     }
 
     #[test]
     fn test_part2() {
-        let graph = parse_input(&"COM)B
+        let graph = parse_input("COM)B
 B)C
 C)D
 D)E
@@ -130,7 +560,56 @@ E)J
 J)K
 K)L
 K)YOU
-I)SAN".to_string()).unwrap();
+I)SAN").unwrap();
         assert_eq!(part2(&graph).unwrap(), 4);
     }
+
+    //
+    // Parses, checks and solves a generated map of a million orbits, then
+    // does the same with the map keyed by name, as it was before
+    // interning. This is an ignored test rather than a criterion bench
+    // like aoc_2019_01's because the crate is binary-only, so a bench
+    // target couldn't reach these functions:
+    //
+    //     cargo test --release -- --ignored --nocapture bench_large_map
+    //
+    #[test]
+    #[ignore]
+    fn bench_large_map() {
+        const BODIES: usize = 1_000_000;
+
+        // every body orbits a random earlier one
+        let mut seed: u64 = 1;
+        let mut input = String::from("COM)B0\n");
+        for i in 1..BODIES {
+            seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            input += &format!("B{})B{}\n", (seed >> 33) as usize % i, i);
+        }
+        input += "B500000)YOU\nB999999)SAN\n";
+
+        let start = Instant::now();
+        let orbits = parse_input(&input).unwrap();
+        let total = part1(&orbits).unwrap();
+        let transfers = part2(&orbits).unwrap();
+        println!("interned:      {:?}", start.elapsed());
+
+        let start = Instant::now();
+        let mut graph: HashMap<String, Vec<String>> = HashMap::new();
+        let mut parents: HashMap<String, String> = HashMap::new();
+        for line in input.lines() {
+            let (a, b) = line.split_once(')').unwrap();
+            assert!(parents.insert(b.to_string(), a.to_string()).is_none());
+            graph.entry(a.to_string()).or_default().push(b.to_string());
+            graph.entry(b.to_string()).or_default().push(a.to_string());
+        }
+        let by_name = bfs("COM", |node: &&str| graph[*node].iter().map(String::as_str));
+        // one parent each and all reachable from COM makes it a tree
+        assert_eq!(by_name.len(), graph.len());
+        let by_name_total: u64 = by_name.values().map(|depth| *depth as u64).sum();
+        let from_you = bfs("YOU", |node: &&str| graph[*node].iter().map(String::as_str));
+        let by_name_transfers = from_you["SAN"] - 2;
+        println!("keyed by name: {:?}", start.elapsed());
+
+        assert_eq!((by_name_total, by_name_transfers), (total, transfers));
+    }
 }
@@ -1,391 +1,362 @@
+use std::env;
 use std::io::{self};
-use std::collections::VecDeque;
-use std::collections::HashSet;
 use std::iter::*;
-use std::cell::RefCell;
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::thread;
+use aoc_util::permutations::permutations;
+use intcode::IntCode;
 
 type Result<T> = ::std::result::Result<T, Box<dyn ::std::error::Error>>;
 
-#[derive(Debug,PartialEq)]
-enum ParameterType {
-    Ref(usize),
-    Value(i32)
-}
+// Runs the amplifiers with the given phase settings, giving the signal
+// sent to the thrusters; with the flag set every amplifier also logs its
+// outputs to stderr.
+type Run = fn(&[i64], &[usize], bool) -> Result<i64>;
+type Search = fn(&[i64], [usize; 5], Run, bool) -> Result<(i64, [usize; 5])>;
 
-enum Instruction {
-    Add { left_op: ParameterType, right_op: ParameterType, into: ParameterType },
-    Mul { left_op: ParameterType, right_op: ParameterType, into: ParameterType },
-    Input { into: ParameterType },
-    Output { param: ParameterType },
-    JumpIfTrue { cond: ParameterType, to: ParameterType },
-    JumpIfFalse { cond: ParameterType, to: ParameterType },
-    LessThan { left_op: ParameterType, right_op: ParameterType, into: ParameterType },
-    Equals { left_op: ParameterType, right_op: ParameterType, into: ParameterType },
-    Terminate,
-}
+const PART1_PHASES: [usize; 5] = [0, 1, 2, 3, 4];
+const PART2_PHASES: [usize; 5] = [5, 6, 7, 8, 9];
 
-struct IntCode<T: Iterator> {
-    memory: Vec<i32>,
-    address_ptr: usize,
-    input_stream: T,
-    output_buffer: VecDeque<i32>,
-    is_terminated: bool
-}
+fn main() -> Result<()> {
+    env_logger::init();
 
-struct OutputStream<T: Iterator>(IntCode<T>);
-
-impl<T> Iterator for OutputStream<T> where
-    T: Iterator<Item = i32>
-{
-    type Item = i32;
-    fn next(&mut self) -> Option<i32> {
-        if self.0.output_buffer.len() > 0 {
-            self.0.output_buffer.pop_front()
-        } else {
-            self.0.run_to_next_output()
-        }
-    }
-}
+    let mut input = String::new();
+    io::stdin().read_line(&mut input)?;
 
-impl<T> IntCode<T> where
-    T: Iterator<Item = i32> {
-    fn init(memory: &Vec<i32>, input_stream: T) -> IntCode<T> {
-        IntCode {
-            memory: memory.clone(),
-            address_ptr: 0,
-            input_stream: input_stream,
-            output_buffer: VecDeque::new(),
-            is_terminated: false
-        }
-    }
+    let input: Vec<i64> = input
+        .split(",")
+        .filter_map(|s|
+                    s.trim().parse().ok()
+        ).collect();
 
-    fn parse_op_code(input: &i32) -> Result<(u32, VecDeque<ParameterType>)> {
-        let op_code = input % 100;
-        let mut parameter_mode = VecDeque::<ParameterType>::new();
-        let mut parameter_stream = input / 100;
-
-        while parameter_stream > 0 {
-            parameter_mode.push_back(
-                match parameter_stream % 10 {
-                    0 => ParameterType::Ref(0),
-                    1 => ParameterType::Value(0),
-                    _ => { return Err(format!("Invalid OpCode: {}", input).into()) }
-                }
-            );
-            parameter_stream /= 10;
-        }
+    let trace_io = env::args().any(|arg| arg == "--trace-io");
+    let search: Search = if env::args().any(|arg| arg == "--parallel") {
+        parallel_search()?
+    } else {
+        max_signal
+    };
 
-        Ok((op_code as u32, parameter_mode))
-    }
+    let (signal, phases) = search(&input, PART1_PHASES, run_amps, trace_io)?;
+    println!("Part1: {} with phases {}", signal, phase_list(&phases));
 
-    fn output_stream(self) -> OutputStream<T> {
-        OutputStream(self)
-    }
+    let (signal, phases) = search(&input, PART2_PHASES, run_amps_part2, trace_io)?;
+    println!("Part2: {} with phases {}", signal, phase_list(&phases));
 
-    fn run_to_next_output(&mut self) -> Option<i32> {
-        while self.output_buffer.len() == 0 && self.is_terminated == false {
-            // bad code; output iterator should be a result
-            self.run_tick().unwrap();
-        }
+    Ok(())
+}
 
-        println!("{:?}", self.output_buffer);
-        self.output_buffer.pop_front()
+//
+// Chains one amplifier per phase setting, each reading its phase and then
+// the previous amplifier's output; the first reads 0 instead.
+//
+fn run_amps(input: &[i64], phase_settings: &[usize], trace: bool) -> Result<i64> {
+    if phase_settings.is_empty() {
+        return Err("No amplifiers".into());
     }
 
-    fn read_parameter(
-        &mut self,
-        parameter_mode: &mut VecDeque<ParameterType>,
-        is_writing: bool // If parameter is for a write operation, parameter type must be a reference
-    ) -> Result<ParameterType> {
-        let parameter_value = self.memory.get(self.address_ptr).ok_or("Invalid Address, address pointer out of bounds when reading parameter")?;
-        let parameter_type = parameter_mode.pop_front().unwrap_or(ParameterType::Ref(0));
-
-        self.address_ptr = self.address_ptr + 1;
-
-        match parameter_type {
-            ParameterType::Ref(_) => {
-                Ok(ParameterType::Ref(*parameter_value as usize))
-            },
-            ParameterType::Value(_) => {
-                if is_writing {
-                    Err("Invalid parameter type: parameter is for a write operation".into())
-                } else {
-                    Ok(ParameterType::Value(*parameter_value))
-                }
-            }
+    let mut signal: Box<dyn Iterator<Item = i64> + '_> = Box::new(once(0));
+    for (index, phase) in phase_settings.iter().enumerate() {
+        let mut amp = IntCode::init(input, once(*phase as i64).chain(signal));
+        if trace {
+            trace_io(&mut amp, index);
         }
+        signal = Box::new(amp.output_stream());
     }
 
-    fn read_instruction(&mut self) -> Result<(Instruction)> {
-        let op_code = self.memory.get(self.address_ptr).ok_or("Invalid Address, address pointer out of bounds when reading instruction")?;
-        self.address_ptr = self.address_ptr + 1;
-
-        let (op_code, mut parameter_mode) = IntCode::<T>::parse_op_code(op_code)?;
-
-        let instruction = match op_code {
-            1 => {
-                Instruction::Add {
-                    left_op: self.read_parameter(&mut parameter_mode, false)?,
-                    right_op: self.read_parameter(&mut parameter_mode, false)?,
-                    into: self.read_parameter(&mut parameter_mode, true)?
-                }
-            }
-            2 => {
-                Instruction::Mul {
-                    left_op: self.read_parameter(&mut parameter_mode, false)?,
-                    right_op: self.read_parameter(&mut parameter_mode, false)?,
-                    into: self.read_parameter(&mut parameter_mode, true)?
-                }
-            }
-            3 => {
-                Instruction::Input {
-                    into: self.read_parameter(&mut parameter_mode, true)?
-                }
-            },
-            4 => {
-                Instruction::Output {
-                    param: self.read_parameter(&mut parameter_mode, false)?
-                }
-            }
-            5 => {
-                Instruction::JumpIfTrue {
-                    cond: self.read_parameter(&mut parameter_mode, false)?,
-                    to: self.read_parameter(&mut parameter_mode, false)?
-                }
-            }
-            6 => {
-                Instruction::JumpIfFalse {
-                    cond: self.read_parameter(&mut parameter_mode, false)?,
-                    to: self.read_parameter(&mut parameter_mode, false)?
-                }
-            }
-            7 => {
-                Instruction::LessThan {
-                    left_op: self.read_parameter(&mut parameter_mode, false)?,
-                    right_op: self.read_parameter(&mut parameter_mode, false)?,
-                    into: self.read_parameter(&mut parameter_mode, true)?
-                }
-            },
-            8 => {
-                Instruction::Equals {
-                    left_op: self.read_parameter(&mut parameter_mode, false)?,
-                    right_op: self.read_parameter(&mut parameter_mode, false)?,
-                    into: self.read_parameter(&mut parameter_mode, true)?
-                }
-            }
-            99 => {
-                Instruction::Terminate
-            }
-            _ => {
-                return Err("Invalid Opcode".into());
-            }
-        };
-
-        Ok(instruction)
-    }
+    signal.next().ok_or_else(|| "No output".into())
+}
 
-    fn resolve_parameter_value(&self, parameter: ParameterType) -> Result<i32> {
-        match parameter {
-            ParameterType::Ref(address) => {
-                Ok(*self.memory.get(address).ok_or(format!("Invalid address reference: {}", address))?)
-            },
-            ParameterType::Value(value) => {
-                Ok(value)
-            }
-        }
-    }
+//
+// Logs every output of amplifier `index` to stderr, for --trace-io.
+//
+fn trace_io<T: Iterator<Item = i64>>(amp: &mut IntCode<T>, index: usize) {
+    amp.on_output(move |value| eprintln!("amp {}: {}", index, value));
+}
 
-    fn write_memory(&mut self, into: ParameterType, value: i32) -> Result<()> {
-        match into {
-            ParameterType::Ref(address) => {
-                let into_ref = self.memory.get_mut(address).ok_or(format!("Invalid address reference: {}", address))?;
-                *into_ref = value;
-            },
-            _ => {
-                panic!("")
-            }
-        }
-        Ok(())
-    }
+fn phase_list(phases: &[usize; 5]) -> String {
+    phases.iter().map(|phase| phase.to_string()).collect::<Vec<_>>().join(",")
+}
 
-    fn run_tick(&mut self) -> Result<()> {
-        let instruction = self.read_instruction()?;
-
-        match instruction {
-            Instruction::Add { left_op, right_op, into } => {
-                let sum = self.resolve_parameter_value(left_op)? + self.resolve_parameter_value(right_op)?;
-                self.write_memory(into, sum)?;
-            }
-            Instruction::Mul { left_op, right_op, into } => {
-                let product = self.resolve_parameter_value(left_op)? * self.resolve_parameter_value(right_op)?;
-                self.write_memory(into, product)?;
-            }
-            Instruction::Input { into } => {
-                let input_value = self.input_stream.next().ok_or("Ran out of input")?;
-                self.write_memory(into, input_value)?;
-            }
-            Instruction::Output { param } => {
-                self.output_buffer.push_back(self.resolve_parameter_value(param)?);
-            }
-            Instruction::JumpIfTrue { cond, to } => {
-                let val = self.resolve_parameter_value(cond)?;
-                if val != 0 {
-                    self.address_ptr = self.resolve_parameter_value(to)? as usize;
-                }
-            }
-            Instruction::JumpIfFalse { cond, to } => {
-                let val = self.resolve_parameter_value(cond)?;
-                if val == 0 {
-                    self.address_ptr = self.resolve_parameter_value(to)? as usize;
-                }
-            }
-            Instruction::LessThan { left_op, right_op, into } => {
-                let less_than = if self.resolve_parameter_value(left_op)? < self.resolve_parameter_value(right_op)? {
-                    1
-                } else { 0 };
-                self.write_memory(into, less_than)?;
-            }
-            Instruction::Equals { left_op, right_op, into } => {
-                let equals = if self.resolve_parameter_value(left_op)? == self.resolve_parameter_value(right_op)? {
-                    1
-                } else { 0 };
-                self.write_memory(into, equals)?;
-            }
-            Instruction::Terminate => {
-                self.is_terminated = true;
-            }
-        };
-
-        Ok(())
-    }
+//
+// Of two results, the one with the higher signal, or on a tie the one
+// with the lexicographically smaller phase settings.
+//
+fn better<const N: usize>(a: (i64, [usize; N]), b: (i64, [usize; N])) -> (i64, [usize; N]) {
+    if b.0 > a.0 || (b.0 == a.0 && b.1 < a.1) { b } else { a }
+}
 
-    fn run_to_termination(&mut self) -> Result<()> {
-        while self.is_terminated == false {
-            self.run_tick()?;
-        }
-        Ok(())
+fn to_array<const N: usize>(ordering: &[usize]) -> [usize; N] {
+    let mut phase_settings = [0; N];
+    phase_settings.copy_from_slice(ordering);
+    phase_settings
+}
+
+//
+// The highest signal over every ordering of `phases`, and the ordering
+// that produced it; of several orderings giving the same signal, the
+// lexicographically smallest. A failing run fails the whole search.
+//
+fn max_signal<const N: usize>(input: &[i64], phases: [usize; N], run: Run, trace: bool) -> Result<(i64, [usize; N])> {
+    let mut best = None;
+    for ordering in permutations(&phases) {
+        let candidate = (run(input, &ordering, trace)?, to_array(&ordering));
+        best = Some(match best {
+            None => candidate,
+            Some(best) => better(best, candidate)
+        });
     }
+    Ok(best.expect("there is always at least one ordering"))
 }
 
-fn main() -> Result<()> {
-    let mut input = String::new();
-    io::stdin().read_line(&mut input)?;
+//
+// `max_signal` with the orderings spread over rayon's threads, each
+// running its own machines. The result is the same, down to which error
+// is reported when several runs fail: the one for the earliest ordering.
+//
+#[cfg(feature = "parallel")]
+fn max_signal_parallel<const N: usize>(input: &[i64], phases: [usize; N], run: Run, trace: bool) -> Result<(i64, [usize; N])> {
+    use rayon::prelude::*;
+
+    let orderings: Vec<Vec<usize>> = permutations(&phases).collect();
+    let best = orderings.par_iter().enumerate()
+        .map(|(index, ordering)| match run(input, ordering, trace) {
+            Ok(signal) => Ok((signal, to_array(ordering))),
+            Err(e) => Err((index, e.to_string()))
+        })
+        .reduce_with(|a, b| match (a, b) {
+            (Ok(a), Ok(b)) => Ok(better(a, b)),
+            (Err(a), Err(b)) => Err(if a.0 < b.0 { a } else { b }),
+            (Err(e), _) | (_, Err(e)) => Err(e)
+        })
+        .expect("there is always at least one ordering");
+    best.map_err(|(_, e)| e.into())
+}
 
-    let input: Vec<i32> = input
-        .split(",")
-        .filter_map(|s|
-                    s.trim().parse().ok()
-        ).collect();
+#[cfg(feature = "parallel")]
+fn parallel_search() -> Result<Search> {
+    Ok(max_signal_parallel)
+}
 
-    Ok(())
+#[cfg(not(feature = "parallel"))]
+fn parallel_search() -> Result<Search> {
+    Err("--parallel needs the `parallel` feature: cargo run --features parallel -- --parallel".into())
 }
 
-fn run_amps(input: &Vec<i32>, phase_settings: &Vec<usize>) -> Result<i32> {
-    let amp_0 = IntCode::init(&input,
-                              once(phase_settings[0] as i32)
-                              .chain(once(0)));
-    let amp_1 = IntCode::init(&input,
-                              once(phase_settings[1] as i32)
-                              .chain(amp_0.output_stream()));
-    let amp_2 = IntCode::init(&input,
-                              once(phase_settings[2] as i32)
-                              .chain(amp_1.output_stream()));
-    let amp_3 = IntCode::init(&input,
-                              once(phase_settings[3] as i32)
-                              .chain(amp_2.output_stream()));
-    let amp_4 = IntCode::init(&input,
-                              once(phase_settings[4] as i32)
-                              .chain(amp_3.output_stream()));
-
-    amp_4.output_stream().next().ok_or("No output".into())
+#[allow(dead_code)]
+fn part1_with_phases(input: &[i64]) -> Result<(i64, [usize; 5])> {
+    max_signal(input, PART1_PHASES, run_amps, false)
 }
 
-fn all_permutation(input: &Vec<i32>, collection: &mut HashSet<usize>, builder: &mut Vec<usize>, f: &dyn Fn(&Vec<i32>, &Vec<usize>) -> Result<i32>) -> i32 {
-    let items: Vec<usize> = collection.iter().cloned().collect();
+#[allow(dead_code)]
+fn part1(input: &[i64]) -> Result<i64> {
+    Ok(part1_with_phases(input)?.0)
+}
 
-    if collection.len() == 0 {
-        let tr = f(input, builder).unwrap_or(<i32>::min_value());
-        return tr;
+//
+// Runs one amplifier of the feedback loop to termination, passing each
+// output on to the next amplifier as soon as it is produced. Returns the
+// last value sent, if any. Errors become strings so they can leave the
+// thread; a closed channel just means the next amplifier has halted.
+//
+fn run_amp(program: Vec<i64>, index: usize, inputs: impl Iterator<Item = i64>, next: Sender<i64>, trace: bool) -> ::std::result::Result<Option<i64>, String> {
+    let mut amp = IntCode::init(&program, inputs);
+    if trace {
+        trace_io(&mut amp, index);
     }
-
-    let mut max: i32 = <i32>::min_value();
-
-    for ele in items {
-        collection.remove(&ele);
-        builder.push(ele);
-
-        let curr = all_permutation(input, collection, builder, f);
-        if curr > max {
-            max = curr;
+    let mut last = None;
+    while !amp.is_terminated() {
+        amp.run_tick().map_err(|e| e.to_string())?;
+        for value in amp.take_outputs() {
+            last = Some(value);
+            let _ = next.send(value);
         }
+    }
+    Ok(last)
+}
 
-        builder.pop();
-        collection.insert(ele);
+//
+// The amplifiers, one per phase setting, run on their own threads and
+// are joined in a ring by channels: the first starts on its phase and 0,
+// then reads whatever the last one sends. An amplifier halting closes
+// its channel, so the ones waiting on it stop with an error instead of
+// blocking forever.
+//
+fn run_amps_part2(input: &[i64], phase_settings: &[usize], trace: bool) -> Result<i64> {
+    if phase_settings.is_empty() {
+        return Err("No amplifiers".into());
     }
 
-    max
-}
+    let count = phase_settings.len();
+    let (senders, receivers): (Vec<Sender<i64>>, Vec<Receiver<i64>>) = (0..count).map(|_| channel()).unzip();
+
+    let mut amps = Vec::new();
+    for (index, receiver) in receivers.into_iter().enumerate() {
+        let program = input.to_vec();
+        let phase = phase_settings[index] as i64;
+        let seed = if index == 0 { Some(0) } else { None };
+        let next = senders[(index + 1) % count].clone();
+        amps.push(thread::spawn(move || {
+            let inputs = once(phase).chain(seed).chain(receiver);
+            run_amp(program, index, inputs, next, trace)
+        }));
+    }
+    // only the amplifiers may hold senders, or the ring never closes
+    drop(senders);
 
-fn part1(input: &Vec<i32>) -> i32 {
-    let mut collection: HashSet<usize> = (0..5).collect();
-    all_permutation(input, &mut collection, &mut vec![], &run_amps)
+    let mut last = None;
+    for amp in amps {
+        last = amp.join().map_err(|_| "amplifier thread panicked")??;
+    }
+    last.ok_or_else(|| "No output".into())
 }
 
-fn run_amps_part2(input: &Vec<i32>, phase_settings: &Vec<usize>) -> Result<i32> {
-    // adapted from https://github.com/Awfa/advent_of_code_2019/blob/master/src/day7.rs
-    let pipe = RefCell::new(VecDeque::<i32>::new());
-
-    let amp_0 = IntCode::init(&input,
-                              once(phase_settings[0] as i32)
-                              .chain(once(0))
-                              .chain(from_fn(|| {
-                                  Some(pipe.borrow_mut().pop_front().unwrap())
-                              })));
-    let amp_1 = IntCode::init(&input,
-                              once(phase_settings[1] as i32)
-                              .chain(amp_0.output_stream()));
-    let amp_2 = IntCode::init(&input,
-                              once(phase_settings[2] as i32)
-                              .chain(amp_1.output_stream()));
-    let amp_3 = IntCode::init(&input,
-                              once(phase_settings[3] as i32)
-                              .chain(amp_2.output_stream()));
-    let amp_4 = IntCode::init(&input,
-                              once(phase_settings[4] as i32)
-                              .chain(amp_3.output_stream()));
-    let amp_4_output = amp_4.output_stream().map(|value| {
-        pipe.borrow_mut().push_back(value);
-        value
-    });
-    amp_4_output.last().ok_or("No output".into())
+#[allow(dead_code)]
+fn part2_with_phases(input: &[i64]) -> Result<(i64, [usize; 5])> {
+    max_signal(input, PART2_PHASES, run_amps_part2, false)
 }
 
-fn part2(input: &Vec<i32>) -> i32 {
-    let mut collection: HashSet<usize> = (5..10).collect();
-    all_permutation(input, &mut collection, &mut vec![], &run_amps_part2)
+#[allow(dead_code)]
+fn part2(input: &[i64]) -> Result<i64> {
+    Ok(part2_with_phases(input)?.0)
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
+    #[cfg(feature = "parallel")]
+    use std::time::Instant;
 
     #[test]
     fn test_amp() {
-        assert_eq!(run_amps(&vec![3,15,3,16,1002,16,10,16,1,16,15,15,4,15,99,0,0], &vec![4,3,2,1,0]).unwrap(), 43210);
-        assert_eq!(run_amps(&vec![3,23,3,24,1002,24,10,24,1002,23,-1,23,101,5,23,23,1,24,23,23,4,23,99,0,0], &vec![0,1,2,3,4]).unwrap(), 54321);
-        assert_eq!(run_amps(&vec![3,31,3,32,1002,32,10,32,1001,31,-2,31,1007,31,0,33,1002,33,7,33,1,33,31,31,1,32,31,31,4,31,99,0,0,0], &vec![1,0,4,3,2]).unwrap(), 65210);
+        assert_eq!(run_amps(&vec![3,15,3,16,1002,16,10,16,1,16,15,15,4,15,99,0,0], &[4,3,2,1,0], false).unwrap(), 43210);
+        assert_eq!(run_amps(&vec![3,23,3,24,1002,24,10,24,1002,23,-1,23,101,5,23,23,1,24,23,23,4,23,99,0,0], &[0,1,2,3,4], false).unwrap(), 54321);
+        assert_eq!(run_amps(&vec![3,31,3,32,1002,32,10,32,1001,31,-2,31,1007,31,0,33,1002,33,7,33,1,33,31,31,1,32,31,31,4,31,99,0,0,0], &[1,0,4,3,2], false).unwrap(), 65210);
+    }
+
+    #[test]
+    fn test_amp_count() {
+        // outputs ten times its input signal plus its phase
+        let program = [3,15,3,16,1002,16,10,16,1,16,15,15,4,15,99,0,0];
+        assert_eq!(run_amps(&program, &[4,3,2], false).unwrap(), 432);
+        assert_eq!(run_amps(&program, &[7], false).unwrap(), 7);
+        assert_eq!(run_amps(&program, &[1,2,3,4,5,6,7,8,9], false).unwrap(), 123456789);
+        assert_eq!(run_amps(&program, &[], false).unwrap_err().to_string(), "No amplifiers");
+
+        // adds its phase to its input signal once; the last amp's output
+        // goes back to the first, which has already halted
+        let program = [3,20,3,21,1,20,21,21,4,21,99];
+        assert_eq!(run_amps_part2(&program, &[1,2,3], false).unwrap(), 6);
+        assert_eq!(run_amps_part2(&program, &[5], false).unwrap(), 5);
+        assert_eq!(run_amps_part2(&program, &[], false).unwrap_err().to_string(), "No amplifiers");
     }
 
     #[test]
     fn test_part1() {
-        assert_eq!(part1(&vec![3,15,3,16,1002,16,10,16,1,16,15,15,4,15,99,0,0]), 43210);
-        assert_eq!(part1(&vec![3,23,3,24,1002,24,10,24,1002,23,-1,23,101,5,23,23,1,24,23,23,4,23,99,0,0]), 54321);
-        assert_eq!(part1(&vec![3,31,3,32,1002,32,10,32,1001,31,-2,31,1007,31,0,33,1002,33,7,33,1,33,31,31,1,32,31,31,4,31,99,0,0,0]), 65210);
+        assert_eq!(part1(&vec![3,15,3,16,1002,16,10,16,1,16,15,15,4,15,99,0,0]).unwrap(), 43210);
+        assert_eq!(part1(&vec![3,23,3,24,1002,24,10,24,1002,23,-1,23,101,5,23,23,1,24,23,23,4,23,99,0,0]).unwrap(), 54321);
+        assert_eq!(part1(&vec![3,31,3,32,1002,32,10,32,1001,31,-2,31,1007,31,0,33,1002,33,7,33,1,33,31,31,1,32,31,31,4,31,99,0,0,0]).unwrap(), 65210);
+    }
+
+    #[test]
+    fn test_winning_phases() {
+        assert_eq!(part1_with_phases(&vec![3,15,3,16,1002,16,10,16,1,16,15,15,4,15,99,0,0]).unwrap(), (43210, [4,3,2,1,0]));
+        assert_eq!(part1_with_phases(&vec![3,23,3,24,1002,24,10,24,1002,23,-1,23,101,5,23,23,1,24,23,23,4,23,99,0,0]).unwrap(), (54321, [0,1,2,3,4]));
+        assert_eq!(part1_with_phases(&vec![3,31,3,32,1002,32,10,32,1001,31,-2,31,1007,31,0,33,1002,33,7,33,1,33,31,31,1,32,31,31,4,31,99,0,0,0]).unwrap(), (65210, [1,0,4,3,2]));
+        assert_eq!(part2_with_phases(&vec![3,26,1001,26,-4,26,3,27,1002,27,2,27,1,27,26,27,4,27,1001,28,-1,28,1005,28,6,99,0,0,5]).unwrap(), (139629729, [9,8,7,6,5]));
+    }
+
+    #[test]
+    fn test_phase_ties() {
+        // each amp outputs 1 if its phase is 0, so every ordering ending
+        // in 0 wins
+        let mut program = vec![3,20,3,21,8,20,22,23,4,23,99];
+        program.resize(24, 0);
+        assert_eq!(part1_with_phases(&program).unwrap(), (1, [1,2,3,4,0]));
+    }
+
+    #[test]
+    fn test_wide_signals() {
+        // each amp outputs its input signal times 1000 plus its phase,
+        // which overflows an i32 by the fourth amp
+        let program = vec![3,20,3,21,1002,21,1000,21,1,21,20,21,4,21,99];
+        assert_eq!(part1_with_phases(&program).unwrap(), (4003002001000, [4,3,2,1,0]));
+        assert!(run_amps(&program, &[4,3,2,1,0], false).unwrap() > i32::MAX as i64);
+    }
+
+    #[test]
+    fn test_failing_run() {
+        // reads its phase and halts without an output
+        let program = vec![3,0,99];
+        assert_eq!(part1(&program).unwrap_err().to_string(), "No output");
+    }
+
+    #[test]
+    fn test_feedback_loop_halt() {
+        // halts on phase 7 and echoes every input otherwise; the amps
+        // after the halted one run out of input rather than panicking
+        let program = vec![3,20,1008,20,7,21,1005,21,19,3,20,4,20,1105,1,9,0,0,0,99,0,0];
+        assert!(run_amps_part2(&program, &[5,6,7,8,9], false).is_err());
+        assert!(part2(&program).is_err());
+
+        // every amp halts straight away, so amp 4 never outputs anything
+        assert_eq!(run_amps_part2(&[3,0,99], &[5,6,7,8,9], false).unwrap_err().to_string(), "No output");
     }
 
     #[test]
     fn test_part2() {
-        assert_eq!(part2(&vec![3,26,1001,26,-4,26,3,27,1002,27,2,27,1,27,26,27,4,27,1001,28,-1,28,1005,28,6,99,0,0,5]), 139629729);
-        assert_eq!(part2(&vec![3,52,1001,52,-5,52,3,53,1,52,56,54,1007,54,5,55,1005,55,26,1001,54,-5,54,1105,1,12,1,53,54,53,1008,54,0,55,1001,55,1,55,2,53,55,53,4,53,1001,56,-1,56,1005,56,6,99,0,0,0,0,10]), 18216);
+        assert_eq!(part2(&vec![3,26,1001,26,-4,26,3,27,1002,27,2,27,1,27,26,27,4,27,1001,28,-1,28,1005,28,6,99,0,0,5]).unwrap(), 139629729);
+        assert_eq!(part2(&vec![3,52,1001,52,-5,52,3,53,1,52,56,54,1007,54,5,55,1005,55,26,1001,54,-5,54,1105,1,12,1,53,54,53,1008,54,0,55,1001,55,1,55,2,53,55,53,4,53,1001,56,-1,56,1005,56,6,99,0,0,0,0,10]).unwrap(), 18216);
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn test_parallel_matches_sequential() {
+        let programs: [&[i64]; 4] = [
+            &[3,15,3,16,1002,16,10,16,1,16,15,15,4,15,99,0,0],
+            &[3,23,3,24,1002,24,10,24,1002,23,-1,23,101,5,23,23,1,24,23,23,4,23,99,0,0],
+            &[3,31,3,32,1002,32,10,32,1001,31,-2,31,1007,31,0,33,1002,33,7,33,1,33,31,31,1,32,31,31,4,31,99,0,0,0],
+            &[3,20,3,21,8,20,22,23,4,23,99,0,0,0,0,0,0,0,0,0,0,0,0,0]
+        ];
+        for program in programs.iter() {
+            assert_eq!(max_signal_parallel(program, PART1_PHASES, run_amps, false).unwrap(),
+                       max_signal(program, PART1_PHASES, run_amps, false).unwrap());
+        }
+
+        let programs: [&[i64]; 2] = [
+            &[3,26,1001,26,-4,26,3,27,1002,27,2,27,1,27,26,27,4,27,1001,28,-1,28,1005,28,6,99,0,0,5],
+            &[3,52,1001,52,-5,52,3,53,1,52,56,54,1007,54,5,55,1005,55,26,1001,54,-5,54,1105,1,12,1,53,54,53,1008,54,0,55,1001,55,1,55,2,53,55,53,4,53,1001,56,-1,56,1005,56,6,99,0,0,0,0,10]
+        ];
+        for program in programs.iter() {
+            assert_eq!(max_signal_parallel(program, PART2_PHASES, run_amps_part2, false).unwrap(),
+                       max_signal(program, PART2_PHASES, run_amps_part2, false).unwrap());
+        }
+
+        assert_eq!(max_signal_parallel(&[3,0,99], PART1_PHASES, run_amps, false).unwrap_err().to_string(), "No output");
+    }
+
+    //
+    // Searches the orderings of eight phases, so eight chained amps,
+    // sequentially and in parallel:
+    //
+    //     cargo test --release --features parallel -- --ignored --nocapture bench_parallel_search
+    //
+    #[cfg(feature = "parallel")]
+    #[test]
+    #[ignore]
+    fn bench_parallel_search() {
+        // outputs ten times its input signal plus its phase
+        let program = [3,20,3,21,1002,21,10,21,1,21,20,21,4,21,99];
+        let phases = [0, 1, 2, 3, 4, 5, 6, 7];
+
+        let start = Instant::now();
+        let sequential = max_signal(&program, phases, run_amps, false).unwrap();
+        println!("sequential: {:?}", start.elapsed());
+
+        let start = Instant::now();
+        let parallel = max_signal_parallel(&program, phases, run_amps, false).unwrap();
+        println!("parallel:   {:?}", start.elapsed());
+
+        assert_eq!(sequential, (76543210, [7, 6, 5, 4, 3, 2, 1, 0]));
+        assert_eq!(parallel, sequential);
     }
 }
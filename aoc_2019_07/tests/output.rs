@@ -0,0 +1,46 @@
+use std::io::Write;
+use std::process::{Command, Output, Stdio};
+
+fn run(args: &[&str]) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_aoc_2019_07"))
+        .args(args)
+        .env_remove("RUST_LOG")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    child.stdin.take().unwrap()
+        .write_all(b"3,15,3,16,1002,16,10,16,1,16,15,15,4,15,99,0,0\n")
+        .unwrap();
+
+    child.wait_with_output().unwrap()
+}
+
+//
+// The amplifier VMs used to print their output buffer on every read;
+// only the two answers should reach stdout.
+//
+#[test]
+fn test_stdout_only_has_answers() {
+    let output = run(&[]);
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "Part1: 43210 with phases 4,3,2,1,0\nPart2: 98765 with phases 9,8,7,6,5\n");
+    assert_eq!(String::from_utf8(output.stderr).unwrap(), "");
+}
+
+//
+// --trace-io shows every amplifier output, on stderr so the answers are
+// still all that stdout has.
+//
+#[test]
+fn test_trace_io() {
+    let output = run(&["--trace-io"]);
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "Part1: 43210 with phases 4,3,2,1,0\nPart2: 98765 with phases 9,8,7,6,5\n");
+
+    let trace = String::from_utf8(output.stderr).unwrap();
+    assert!(trace.lines().any(|line| line == "amp 0: 4"));
+    assert!(trace.lines().any(|line| line == "amp 4: 43210"));
+}
@@ -1,8 +1,13 @@
+use std::env;
+use std::fs;
 use std::io::{self};
 use std::collections::VecDeque;
 use std::collections::HashSet;
 use std::iter::*;
 use std::cell::RefCell;
+use aoc_util::geom::{Direction, Point};
+
+mod ocr;
 
 type Result<T> = ::std::result::Result<T, Box<dyn ::std::error::Error>>;
 
@@ -13,6 +18,7 @@ enum ParameterType {
     Relative(i64)
 }
 
+#[derive(Debug)]
 enum Instruction {
     Add { left_op: ParameterType, right_op: ParameterType, into: ParameterType },
     Mul { left_op: ParameterType, right_op: ParameterType, into: ParameterType },
@@ -305,169 +311,394 @@ fn main() -> Result<()> {
                     s.trim().parse().ok()
         ).collect();
 
+    let options = RenderOptions::from_args(env::args().skip(1))?;
+
     println!("{}", part1(&input)?);
-    part2(&input)?;
 
-    Ok(())
-}
+    // the picture is drawn even when the letters can't be read, as it is
+    // then the only way to get the answer
+    let registration = paint_registration(&input)?;
+    let letters = part2(&registration);
+    if let Ok(letters) = &letters {
+        println!("{}", letters);
+    }
+
+    match &options.image {
+        Some(path) => fs::write(path, render_image(&registration, &options).to_ppm())?,
+        None => print!("{}", render(&registration, &options))
+    }
 
-#[derive(Clone, Copy)]
-enum Direction {
-    Up, Down, Left, Right
+    letters.map(|_| ())
 }
 
-impl Direction {
-    fn value(&self) -> (i32, i32) {
-        match *self {
-            Direction::Up => (-1, 0),
-            Direction::Down => (1, 0),
-            Direction::Left => (0, -1),
-            Direction::Right => (0, 1)
-        }
+fn turn(dir: Direction, new_dir: i64) -> Direction {
+    if new_dir == 0 {
+        dir.left()
+    } else if new_dir == 1 {
+        dir.right()
+    } else {
+        panic!("Bad direction given");
     }
-    fn curr_index(&self) -> usize {
-        match *self {
-            Direction::Up => 0,
-            Direction::Right => 1,
-            Direction::Down => 2,
-            Direction::Left => 3
-        }
+}
+
+//
+// The hull and the robot on it: the cells painted with color 1, where
+// the robot stands and which way it faces. A run can start from any hull,
+// such as one left behind by an earlier run.
+//
+#[derive(Debug, Clone, PartialEq)]
+struct Hull {
+    black_cells: HashSet<Point>,
+    position: Point,
+    dir: Direction
+}
+
+impl Hull {
+    fn new() -> Hull {
+        Hull { black_cells: HashSet::new(), position: Point::origin(), dir: Direction::Up }
     }
-    fn mutate_direction(self, new_dir: i64, cur_y: i32, cur_x: i32) -> (Direction, i32, i32) {
-        const directions: [Direction; 4] = [Direction::Up, Direction::Right, Direction::Down, Direction::Left];
-        if new_dir == 0 {
-            let new_direction = directions[(self.curr_index() + 3) % 4];
-            let (dy, dx) = new_direction.value();
-            (new_direction, cur_y + dy, cur_x + dx)
-        } else if new_dir == 1 {
-            let new_direction = directions[(self.curr_index() + 1) % 4];
-            let (dy, dx) = new_direction.value();
-            (new_direction, cur_y + dy, cur_x + dx)
+}
+
+//
+// Moves a robot may make before it is assumed to be stuck in a loop;
+// real programs halt after around ten thousand.
+//
+const DEFAULT_MAX_STEPS: usize = 1_000_000;
+
+//
+// Runs the painting robot on `hull` until the program halts. Returns the
+// hull as the robot left it, and how many cells it painted with color 1
+// at least once. A program still going after `max_steps` moves is an
+// error, so a runaway robot can't grow the hull forever.
+//
+fn run_robot(input: &Vec<i64>, hull: Hull, max_steps: usize) -> Result<(Hull, usize)> {
+    let Hull { black_cells, position, mut dir } = hull;
+    let black_cells = RefCell::new(black_cells);
+    let position = RefCell::new(position);
+    let mut ever_painted = HashSet::<Point>::new();
+
+    let machine = IntCode::init(input,
+                                from_fn(|| {
+                                    if black_cells.borrow().contains(&*position.borrow()) {
+                                        Some(1)
+                                    } else {
+                                        Some(0)
+                                    }
+                                }));
+
+    let mut output_stream = machine.output_stream();
+    let mut steps = 0;
+
+    while let Some(color) = output_stream.next() {
+        let cur = *position.borrow();
+        if color == 1 {
+            black_cells.borrow_mut().insert(cur);
+            ever_painted.insert(cur);
         } else {
-            panic!("Bad direction given");
+            black_cells.borrow_mut().remove(&cur);
         }
+
+        // a trailing color without a turn means the program is done
+        let next_dir = match output_stream.next() {
+            Some(next_dir) => next_dir,
+            None => break
+        };
+
+        if steps == max_steps {
+            return Err(format!("Robot still moving after {} steps", max_steps).into());
+        }
+        steps += 1;
+
+        dir = turn(dir, next_dir);
+        *position.borrow_mut() = cur.step(dir);
     }
+
+    drop(output_stream);
+    let hull = Hull { black_cells: black_cells.into_inner(), position: position.into_inner(), dir };
+    Ok((hull, ever_painted.len()))
 }
 
 fn part1(input: &Vec<i64>) -> Result<i64> {
-    let mut black_cells = RefCell::new(HashSet::<(i32, i32)>::new());
-    let mut ever_painted = HashSet::<(i32, i32)>::new();
-    let mut cur_x: RefCell<i32> = RefCell::new(0);
-    let mut cur_y: RefCell<i32> = RefCell::new(0);
-    let mut dir = Direction::Up;
-
-    let mut machine = IntCode::init(input,
-                                    once(0)
-                                    .chain(from_fn(|| {
-                                        if black_cells.borrow().contains(&(*cur_y.borrow(), *cur_x.borrow())) {
-                                            Some(1)
-                                        } else {
-                                            Some(0)
-                                        }
-                                    })));
+    let (_, painted) = run_robot(input, Hull::new(), DEFAULT_MAX_STEPS)?;
+    Ok(painted as i64)
+}
 
-    let mut output_stream = machine.output_stream();
-    let mut part1_ans = 0;
-
-    loop {
-        if let Some(color) = output_stream.next() {
-            if color == 1 {
-                black_cells.borrow_mut().insert((*cur_y.borrow(), *cur_x.borrow()));
-                if !ever_painted.contains(&(*cur_y.borrow(), *cur_x.borrow())) {
-                    part1_ans = part1_ans + 1;
-                    ever_painted.insert((*cur_y.borrow(), *cur_x.borrow()));
-                }
-            } else {
-                black_cells.borrow_mut().remove(&(*cur_y.borrow(), *cur_x.borrow()));
-            }
+//
+// How to draw the painted hull. Rows run along y and columns along x, from
+// the smallest coordinate to the largest unless flipped. The defaults
+// match what part 2 has always printed. With `image` set the hull is
+// written there instead, each cell drawn as a `scale` by `scale` block.
+//
+struct RenderOptions {
+    flip_y: bool,
+    flip_x: bool,
+    on: char,
+    off: char,
+    image: Option<String>,
+    scale: usize
+}
 
-            let next_dir = output_stream.next().unwrap();
+impl Default for RenderOptions {
+    fn default() -> RenderOptions {
+        RenderOptions { flip_y: false, flip_x: false, on: '#', off: '.', image: None, scale: 1 }
+    }
+}
 
-            let (new_dir, new_cur_y, new_cur_x) = dir.mutate_direction(next_dir, *cur_y.borrow(), *cur_x.borrow());
-            *cur_y.borrow_mut() = new_cur_y;
-            *cur_x.borrow_mut() = new_cur_x;
-            dir = new_dir;
-        } else {
-            break;
+impl RenderOptions {
+    //
+    // Reads `--flip-y`, `--flip-x`, `--on=C`, `--off=C`, `--image FILE`
+    // and `--scale=N`.
+    //
+    fn from_args<I: Iterator<Item = String>>(mut args: I) -> Result<RenderOptions> {
+        let mut options = RenderOptions::default();
+
+        while let Some(arg) = args.next() {
+            if arg == "--flip-y" {
+                options.flip_y = true;
+            } else if arg == "--flip-x" {
+                options.flip_x = true;
+            } else if let Some(glyph) = arg.strip_prefix("--on=") {
+                options.on = parse_glyph(glyph)?;
+            } else if let Some(glyph) = arg.strip_prefix("--off=") {
+                options.off = parse_glyph(glyph)?;
+            } else if arg == "--image" {
+                options.image = Some(args.next().ok_or("--image needs a file name")?);
+            } else if let Some(scale) = arg.strip_prefix("--scale=") {
+                options.scale = match scale.parse() {
+                    Ok(scale) if scale > 0 => scale,
+                    _ => return Err(format!("Expected a positive scale, got {:?}", scale).into())
+                };
+            } else {
+                return Err(format!("Unknown argument: {}", arg).into());
+            }
         }
+
+        Ok(options)
     }
+}
 
-    Ok(part1_ans)
+fn parse_glyph(glyph: &str) -> Result<char> {
+    let mut chars = glyph.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => Ok(c),
+        _ => Err(format!("Expected a single character, got {:?}", glyph).into())
+    }
 }
 
-fn part2(input: &Vec<i64>) -> Result<()> {
-    let black_cells = RefCell::new(HashSet::<(i32, i32)>::new());
-    let cur_x: RefCell<i32> = RefCell::new(0);
-    let cur_y: RefCell<i32> = RefCell::new(0);
-    let mut dir = Direction::Up;
+//
+// The rows and columns of the bounding box of the painted cells, in the
+// order they are drawn.
+//
+fn axes(black_cells: &HashSet<Point>, options: &RenderOptions) -> (Vec<i32>, Vec<i32>) {
+    if black_cells.is_empty() {
+        return (Vec::new(), Vec::new());
+    }
 
-    let machine = IntCode::init(input,
-                                once(1)
-                                .chain(from_fn(|| {
-                                    if black_cells.borrow().contains(&(*cur_y.borrow(), *cur_x.borrow())) {
-                                        Some(1)
-                                    } else {
-                                        Some(0)
-                                    }
-                                })));
+    let min_y = black_cells.iter().map(|p| p.y).min().unwrap();
+    let max_y = black_cells.iter().map(|p| p.y).max().unwrap();
+    let min_x = black_cells.iter().map(|p| p.x).min().unwrap();
+    let max_x = black_cells.iter().map(|p| p.x).max().unwrap();
+
+    let ys: Vec<i32> = if options.flip_y {
+        (min_y..=max_y).rev().collect()
+    } else {
+        (min_y..=max_y).collect()
+    };
+    let xs: Vec<i32> = if options.flip_x {
+        (min_x..=max_x).rev().collect()
+    } else {
+        (min_x..=max_x).collect()
+    };
+    (ys, xs)
+}
 
-    let mut output_stream = machine.output_stream();
+fn render(black_cells: &HashSet<Point>, options: &RenderOptions) -> String {
+    let (ys, xs) = axes(black_cells, options);
 
-    loop {
-        if let Some(color) = output_stream.next() {
-            if color == 1 {
-                black_cells.borrow_mut().insert((*cur_y.borrow(), *cur_x.borrow()));
+    let mut image = String::new();
+    for y in &ys {
+        for x in &xs {
+            if black_cells.contains(&Point::new(*x, *y)) {
+                image.push(options.on);
             } else {
-                black_cells.borrow_mut().remove(&(*cur_y.borrow(), *cur_x.borrow()));
+                image.push(options.off);
             }
+        }
+        image.push('\n');
+    }
+    image
+}
 
-            let next_dir = output_stream.next().unwrap();
+//
+// An RGB image, three bytes a pixel, row by row from the top left.
+//
+#[derive(Debug, PartialEq)]
+struct Image {
+    width: usize,
+    height: usize,
+    pixels: Vec<u8>
+}
 
-            let (new_dir, new_cur_y, new_cur_x) = dir.mutate_direction(next_dir, *cur_y.borrow(), *cur_x.borrow());
-            *cur_y.borrow_mut() = new_cur_y;
-            *cur_x.borrow_mut() = new_cur_x;
-            dir = new_dir;
-        } else {
-            break;
-        }
+impl Image {
+    //
+    // As a binary PPM file, which needs no encoder and most viewers open.
+    //
+    fn to_ppm(&self) -> Vec<u8> {
+        let mut ppm = format!("P6\n{} {}\n255\n", self.width, self.height).into_bytes();
+        ppm.extend_from_slice(&self.pixels);
+        ppm
     }
+}
 
-    let mut min_y = i32::max_value();
-    let mut min_x = i32::max_value();
-    let mut max_y = i32::min_value();
-    let mut max_x = i32::min_value();
-    for (y, x) in &(*black_cells.borrow()) {
-        if y > &max_y {
-            max_y = *y;
-        }
-        if y < &min_y {
-            min_y = *y;
+//
+// The painted cells in white on black, cropped and flipped the same way
+// as `render`.
+//
+fn render_image(black_cells: &HashSet<Point>, options: &RenderOptions) -> Image {
+    let (ys, xs) = axes(black_cells, options);
+    let scale = options.scale;
+    let width = xs.len() * scale;
+    let height = ys.len() * scale;
+
+    let mut pixels = Vec::with_capacity(width * height * 3);
+    for y in &ys {
+        let mut row = Vec::with_capacity(width * 3);
+        for x in &xs {
+            let shade = if black_cells.contains(&Point::new(*x, *y)) { 255 } else { 0 };
+            row.extend(repeat_n(shade, scale * 3));
         }
-        if x > &max_x {
-            max_x = *x;
-        }
-        if x < &min_x {
-            min_x = *x;
+        for _ in 0..scale {
+            pixels.extend_from_slice(&row);
         }
     }
 
-    for y in min_y..=max_y {
-        for x in min_x..=max_x {
-            if black_cells.borrow().contains(&(y, x)) {
-                print!("#")
-            } else {
-                print!(".")
-            }
-        }
-        println!("");
-    }
+    Image { width, height, pixels }
+}
 
-    Ok(())
+//
+// The hull after the robot runs from a single painted cell.
+//
+fn paint_registration(input: &Vec<i64>) -> Result<HashSet<Point>> {
+    let mut hull = Hull::new();
+    hull.black_cells.insert(Point::origin());
+
+    let (hull, _) = run_robot(input, hull, DEFAULT_MAX_STEPS)?;
+    Ok(hull.black_cells)
+}
 
+//
+// The letters of the registration identifier.
+//
+fn part2(registration: &HashSet<Point>) -> Result<String> {
+    ocr::recognize(registration)
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
 
+    #[test]
+    fn test_lone_trailing_output() {
+        // paints the starting cell, then halts without turning
+        let program = vec![104,1,99];
+        assert_eq!(part1(&program).unwrap(), 1);
+        assert_eq!(render(&paint_registration(&program).unwrap(), &RenderOptions::default()), "#\n");
+        assert!(part2(&paint_registration(&program).unwrap()).is_err());
+    }
+
+    #[test]
+    fn test_run_robot_from_painted_hull() {
+        // reads the color under the robot; on 0 it paints 1 and turns
+        // left, on 1 it paints 0 and turns right
+        let program = vec![3,16,1005,16,11,104,1,104,0,99,0,104,0,104,1,99,0];
+
+        let (hull, painted) = run_robot(&program, Hull::new(), DEFAULT_MAX_STEPS).unwrap();
+        assert_eq!(painted, 1);
+        assert_eq!(hull.black_cells, [Point::origin()].iter().copied().collect());
+        assert_eq!((hull.position, hull.dir), (Point::new(-1, 0), Direction::Left));
+
+        // starting on the cell it just painted, it takes the other branch
+        let restart = Hull { position: Point::origin(), dir: Direction::Up, ..hull };
+        let (hull, painted) = run_robot(&program, restart, DEFAULT_MAX_STEPS).unwrap();
+        assert_eq!(painted, 0);
+        assert!(hull.black_cells.is_empty());
+        assert_eq!((hull.position, hull.dir), (Point::new(1, 0), Direction::Right));
+
+        let seeded = Hull { black_cells: [Point::new(5, 5)].iter().copied().collect(), position: Point::new(5, 5), dir: Direction::Down };
+        let (hull, _) = run_robot(&program, seeded, DEFAULT_MAX_STEPS).unwrap();
+        assert_eq!((hull.position, hull.dir), (Point::new(4, 5), Direction::Left));
+    }
+
+    #[test]
+    fn test_max_steps() {
+        // paints and turns right forever without reading the hull
+        let program = vec![104,1,104,1,1105,1,0];
+        let error = run_robot(&program, Hull::new(), 100).unwrap_err();
+        assert_eq!(error.to_string(), "Robot still moving after 100 steps");
+
+        // exactly enough steps is fine
+        let program = vec![104,1,104,1,104,1,104,1,99];
+        let (hull, painted) = run_robot(&program, Hull::new(), 2).unwrap();
+        assert_eq!((hull.position, painted), (Point::new(1, 1), 2));
+        assert!(run_robot(&program, Hull::new(), 1).is_err());
+    }
+
+    #[test]
+    fn test_render() {
+        // ##
+        // #.
+        let cells: HashSet<Point> = [Point::new(0, 0), Point::new(1, 0), Point::new(0, 1)].iter().copied().collect();
+
+        assert_eq!(render(&cells, &RenderOptions::default()), "##\n#.\n");
+
+        let flip_y = RenderOptions { flip_y: true, ..RenderOptions::default() };
+        assert_eq!(render(&cells, &flip_y), "#.\n##\n");
+
+        let flip_x = RenderOptions { flip_x: true, on: 'X', off: ' ', ..RenderOptions::default() };
+        assert_eq!(render(&cells, &flip_x), "XX\n X\n");
+
+        assert_eq!(render(&HashSet::new(), &flip_y), "");
+    }
+
+    #[test]
+    fn test_render_args() {
+        let args = ["--flip-y", "--on=@"].iter().map(|s| s.to_string());
+        let options = RenderOptions::from_args(args).unwrap();
+        assert!(options.flip_y);
+        assert!(!options.flip_x);
+        assert_eq!((options.on, options.off), ('@', '.'));
+
+        assert!(RenderOptions::from_args(once("--on=ab".to_string())).is_err());
+        assert!(RenderOptions::from_args(once("--mirror".to_string())).is_err());
+
+        let args = ["--scale=4", "--image", "hull.ppm"].iter().map(|s| s.to_string());
+        let options = RenderOptions::from_args(args).unwrap();
+        assert_eq!((options.image.as_deref(), options.scale), (Some("hull.ppm"), 4));
+
+        assert!(RenderOptions::from_args(once("--image".to_string())).is_err());
+        assert!(RenderOptions::from_args(once("--scale=0".to_string())).is_err());
+    }
+
+    #[test]
+    fn test_render_image() {
+        // #.
+        // ##
+        let cells: HashSet<Point> = [Point::new(3, 7), Point::new(3, 8), Point::new(4, 8)].iter().copied().collect();
+        let (w, b) = ([255, 255, 255], [0, 0, 0]);
+
+        let image = render_image(&cells, &RenderOptions::default());
+        assert_eq!((image.width, image.height), (2, 2));
+        assert_eq!(image.pixels, [w, b, w, w].concat());
+
+        let scale = RenderOptions { scale: 2, ..RenderOptions::default() };
+        let image = render_image(&cells, &scale);
+        assert_eq!((image.width, image.height), (4, 4));
+        assert_eq!(image.pixels, [w, w, b, b, w, w, b, b, w, w, w, w, w, w, w, w].concat());
+
+        let ppm = render_image(&cells, &RenderOptions::default()).to_ppm();
+        assert!(ppm.starts_with(b"P6\n2 2\n255\n"));
+        assert_eq!(ppm.len(), 11 + 12);
+
+        assert_eq!(render_image(&HashSet::new(), &scale).pixels, []);
+    }
 }
@@ -0,0 +1,135 @@
+//!
+//! Reads the capital letters some puzzles draw instead of printing: each
+//! is 4 cells wide and 6 tall, with a blank column before the next one.
+//!
+
+use std::collections::HashSet;
+use aoc_util::geom::Point;
+
+type Result<T> = ::std::result::Result<T, Box<dyn ::std::error::Error>>;
+
+const WIDTH: i32 = 4;
+const HEIGHT: i32 = 6;
+
+//
+// The letters known to turn up in puzzle answers; the others have never
+// been seen drawn, so there is nothing to match them against.
+//
+const GLYPHS: [(char, [&str; 6]); 17] = [
+    ('A', [".##.", "#..#", "#..#", "####", "#..#", "#..#"]),
+    ('B', ["###.", "#..#", "###.", "#..#", "#..#", "###."]),
+    ('C', [".##.", "#..#", "#...", "#...", "#..#", ".##."]),
+    ('E', ["####", "#...", "###.", "#...", "#...", "####"]),
+    ('F', ["####", "#...", "###.", "#...", "#...", "#..."]),
+    ('G', [".##.", "#..#", "#...", "#.##", "#..#", ".###"]),
+    ('H', ["#..#", "#..#", "####", "#..#", "#..#", "#..#"]),
+    ('I', ["###.", ".#..", ".#..", ".#..", ".#..", "###."]),
+    ('J', ["..##", "...#", "...#", "...#", "#..#", ".##."]),
+    ('K', ["#..#", "#.#.", "##..", "#.#.", "#.#.", "#..#"]),
+    ('L', ["#...", "#...", "#...", "#...", "#...", "####"]),
+    ('O', [".##.", "#..#", "#..#", "#..#", "#..#", ".##."]),
+    ('P', ["###.", "#..#", "#..#", "###.", "#...", "#..."]),
+    ('R', ["###.", "#..#", "#..#", "###.", "#.#.", "#..#"]),
+    ('S', [".###", "#...", "#...", ".##.", "...#", "###."]),
+    ('U', ["#..#", "#..#", "#..#", "#..#", "#..#", ".##."]),
+    ('Z', ["####", "...#", "..#.", ".#..", "#...", "####"]),
+];
+
+//
+// The letters drawn by the painted panels, read left to right. The
+// bounding box is split into cells 5 columns wide, the last of which may
+// lack its blank column.
+//
+pub fn recognize(panels: &HashSet<Point>) -> Result<String> {
+    if panels.is_empty() {
+        return Ok(String::new());
+    }
+
+    let min_x = panels.iter().map(|p| p.x).min().unwrap();
+    let max_x = panels.iter().map(|p| p.x).max().unwrap();
+    let min_y = panels.iter().map(|p| p.y).min().unwrap();
+    let max_y = panels.iter().map(|p| p.y).max().unwrap();
+
+    if max_y - min_y + 1 != HEIGHT {
+        return Err(format!("Letters must be {} rows tall, got {}", HEIGHT, max_y - min_y + 1).into());
+    }
+
+    let mut letters = String::new();
+    for (index, left) in (min_x..=max_x).step_by(WIDTH as usize + 1).enumerate() {
+        let cell: Vec<String> = (min_y..min_y + HEIGHT).map(|y| {
+            (left..left + WIDTH)
+                .map(|x| if panels.contains(&Point::new(x, y)) { '#' } else { '.' })
+                .collect()
+        }).collect();
+
+        let gap_painted = (min_y..min_y + HEIGHT).any(|y| panels.contains(&Point::new(left + WIDTH, y)));
+        let glyph = GLYPHS.iter().find(|(_, rows)| rows.iter().eq(cell.iter()));
+
+        match glyph {
+            Some((letter, _)) if !gap_painted => letters.push(*letter),
+            _ => return Err(format!("Unknown glyph in letter {}:\n{}", index + 1, cell.join("\n")).into())
+        }
+    }
+
+    Ok(letters)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn panels(rows: &[&str], origin: Point) -> HashSet<Point> {
+        let mut panels = HashSet::new();
+        for (y, row) in rows.iter().enumerate() {
+            for (x, c) in row.chars().enumerate() {
+                if c == '#' {
+                    panels.insert(Point::new(origin.x + x as i32, origin.y + y as i32));
+                }
+            }
+        }
+        panels
+    }
+
+    #[test]
+    fn test_letters() {
+        for (letter, rows) in GLYPHS.iter() {
+            assert_eq!(recognize(&panels(rows, Point::origin())).unwrap(), letter.to_string());
+        }
+
+        // anywhere on the hull, and with or without the trailing gap
+        let h = ["#..#.", "#..#.", "####.", "#..#.", "#..#.", "#..#."];
+        assert_eq!(recognize(&panels(&h, Point::new(-7, 3))).unwrap(), "H");
+
+        let hi = ["#..#.###", "#..#..#.", "####..#.", "#..#..#.", "#..#..#.", "#..#.###"];
+        assert_eq!(recognize(&panels(&hi, Point::origin())).unwrap(), "HI");
+
+        assert_eq!(recognize(&HashSet::new()).unwrap(), "");
+    }
+
+    #[test]
+    fn test_word() {
+        let word = [
+            "###...##..#..#.####.###..#....###..###.",
+            "#..#.#..#.#..#.#....#..#.#....#..#.#..#",
+            "#..#.#....#..#.###..#..#.#....#..#.#..#",
+            "###..#.##.#..#.#....###..#....###..###.",
+            "#....#..#.#..#.#....#....#....#....#.#.",
+            "#.....###..##..####.#....####.#....#..#",
+        ];
+        assert_eq!(recognize(&panels(&word, Point::new(1, 0))).unwrap(), "PGUEPLPR");
+    }
+
+    #[test]
+    fn test_unknown_glyphs() {
+        let smudged = ["###.", "#..#", "#..#", "###.", "#.##", "#..#"];
+        let error = recognize(&panels(&smudged, Point::origin())).unwrap_err().to_string();
+        assert_eq!(error, "Unknown glyph in letter 1:\n###.\n#..#\n#..#\n###.\n#.##\n#..#");
+
+        // two letters run together
+        let joined = ["#..##..#", "#..##..#", "########", "#..##..#", "#..##..#", "#..##..#"];
+        assert!(recognize(&panels(&joined, Point::origin())).is_err());
+
+        let short = ["####", "#..#", "####"];
+        assert_eq!(recognize(&panels(&short, Point::origin())).unwrap_err().to_string(), "Letters must be 6 rows tall, got 3");
+    }
+}
@@ -1,7 +1,10 @@
 use std::io::{self};
+use std::env;
 use std::collections::VecDeque;
+use std::convert::TryFrom;
 use std::iter::*;
 use std::cell::RefCell;
+use aoc_util::search::bfs;
 
 type Result<T> = ::std::result::Result<T, Box<dyn ::std::error::Error>>;
 
@@ -12,6 +15,7 @@ enum ParameterType {
     Relative(i64)
 }
 
+#[derive(Debug)]
 enum Instruction {
     Add { left_op: ParameterType, right_op: ParameterType, into: ParameterType },
     Mul { left_op: ParameterType, right_op: ParameterType, into: ParameterType },
@@ -295,6 +299,8 @@ impl<T> IntCode<T> where
 }
 
 fn main() -> Result<()> {
+    env_logger::init();
+
     let mut input = String::new();
     io::stdin().read_line(&mut input)?;
 
@@ -304,12 +310,43 @@ fn main() -> Result<()> {
                     s.trim().parse().ok()
         ).collect();
 
-    let ans = part1_and_2(&input)?;
-    println!("{}\n{}", ans.0, ans.1);
+    let verify = env::args().any(|arg| arg == "--verify");
+    let explored = part1_and_2(&input, verify)?;
+
+    if env::args().any(|arg| arg == "--stats") {
+        let stats = map_stats(&explored.map);
+        println!("rooms: {}, dead ends: {}, junctions: {}, longest corridor: {}",
+                 stats.rooms, stats.dead_ends, stats.junctions, stats.longest_corridor);
+    }
+
+    println!("{}\n{}", explored.part1, explored.part2);
 
     Ok(())
 }
 
+//
+// What the droid reports after each move command.
+//
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum DroidStatus {
+    Wall,
+    Moved,
+    MovedToGoal
+}
+
+impl TryFrom<i64> for DroidStatus {
+    type Error = String;
+
+    fn try_from(output: i64) -> std::result::Result<DroidStatus, String> {
+        match output {
+            0 => Ok(DroidStatus::Wall),
+            1 => Ok(DroidStatus::Moved),
+            2 => Ok(DroidStatus::MovedToGoal),
+            _ => Err(format!("Bad output: {}", output))
+        }
+    }
+}
+
 #[derive(Debug,PartialEq)]
 enum ExploreState {
     Room(usize),
@@ -348,6 +385,15 @@ impl Room {
         }
     }
 
+    fn dir(&self, dir: usize) -> &ExploreState {
+        match dir {
+            UP_INDEX => &self.up,
+            DOWN_INDEX => &self.down,
+            LEFT_INDEX => &self.left,
+            _ => &self.right
+        }
+    }
+
     fn adjacent(&self) -> Vec<usize> {
         let mut rooms = Vec::new();
         if let ExploreState::Room(r) = self.up {
@@ -457,7 +503,23 @@ impl MapState {
     }
 }
 
-fn part1_and_2(input: &Vec<i64>) -> Result<(usize, usize)> {
+//
+// The maze once the droid has explored all of it, with the room the
+// oxygen system is in and both answers.
+//
+struct Exploration {
+    map: MapState,
+    goal_index: usize,
+    part1: usize,
+    part2: usize
+}
+
+//
+// Explores the maze and answers both parts. With `verify` set the map is
+// checked against the droid program before it is trusted, see
+// `verify_map`.
+//
+fn part1_and_2(input: &Vec<i64>, verify: bool) -> Result<Exploration> {
     // the follow code assumes that the maze forms a tree
     let map_state_cell = RefCell::new(MapState::new());
     let is_complete = RefCell::new(false);
@@ -489,21 +551,21 @@ fn part1_and_2(input: &Vec<i64>) -> Result<(usize, usize)> {
     while *is_complete.borrow() == false {
         let result = output.next().unwrap();
 
-        match result {
-            0 => { // Wall
+        match DroidStatus::try_from(result)? {
+            DroidStatus::Wall => {
                 if let Err(e) = map_state_cell.borrow_mut().insert_wall(*last_move.borrow()) {
                     if *is_complete.borrow() == false {
                         return Err(e);
                     }
                 }
             }
-            1 => { // New Room
+            DroidStatus::Moved => {
                 let new_index = map_state_cell.borrow_mut().insert_room_and_move(*last_move.borrow())?;
                 if new_index + 1 == map_state_cell.borrow().last_index() {
                     breadcrumps.borrow_mut().push(MapState::flip(&last_move.borrow()));
                 }
             }
-            2 => { // Goal Room
+            DroidStatus::MovedToGoal => {
                 let new_index = map_state_cell.borrow_mut().insert_room_and_move(*last_move.borrow())?;
                 if new_index + 1 == map_state_cell.borrow().last_index() {
                     breadcrumps.borrow_mut().push(MapState::flip(&last_move.borrow()));
@@ -511,49 +573,235 @@ fn part1_and_2(input: &Vec<i64>) -> Result<(usize, usize)> {
                 goal_index = new_index;
                 part1_answer = breadcrumps.borrow().len();
             }
-            _ => {
-                return Err("Bad output!".into());
+        }
+    }
+
+    drop(output);
+    let map = map_state_cell.into_inner();
+    if verify {
+        verify_map(input, &map, goal_index)?;
+    }
+    let part2_answer = part2(&map, goal_index)?;
+
+    Ok(Exploration { map, goal_index, part1: part1_answer, part2: part2_answer })
+}
+
+const ALL_DIRS: [usize; 4] = [UP_INDEX, DOWN_INDEX, LEFT_INDEX, RIGHT_INDEX];
+
+//
+// Shortest list of moves from the starting room to every room.
+//
+fn paths_from_start(map: &MapState) -> Result<Vec<Vec<usize>>> {
+    let mut paths: Vec<Option<Vec<usize>>> = vec![None; map.0.len()];
+    let mut queue = VecDeque::new();
+
+    paths[0] = Some(Vec::new());
+    queue.push_back(0);
+
+    while let Some(room_index) = queue.pop_front() {
+        let room = map.0.get(room_index).ok_or("Invalid index")?;
+        for dir in ALL_DIRS.iter() {
+            if let ExploreState::Room(r) = *room.dir(*dir) {
+                if paths[r].is_none() {
+                    let mut path = paths[room_index].clone().unwrap();
+                    path.push(*dir);
+                    paths[r] = Some(path);
+                    queue.push_back(r);
+                }
+            }
+        }
+    }
+
+    paths.into_iter().map(|path| path.ok_or_else(|| "Room not reachable from start".into())).collect()
+}
+
+//
+// Walks a fresh droid through every mapped room, checking each move gets
+// the response the map predicts and that every recorded wall is really
+// there. Rooms are visited in the order of their paths from the start,
+// so moving between consecutive rooms only backtracks to the shared
+// part of their paths.
+//
+fn verify_map(input: &Vec<i64>, map: &MapState, goal_index: usize) -> Result<()> {
+    let paths = paths_from_start(map)?;
+    let moves = RefCell::new(VecDeque::new());
+    let machine = IntCode::init(input, from_fn(|| moves.borrow_mut().pop_front()));
+    let mut output = machine.output_stream();
+
+    let mut step = |dir: usize, expected: DroidStatus| -> Result<()> {
+        moves.borrow_mut().push_back(dir as i64);
+        let status = DroidStatus::try_from(output.next().ok_or("Droid stopped responding")?)?;
+        if status != expected {
+            return Err(format!("Moving {} expected status {:?}, got {:?}", dir, expected, status).into());
+        }
+        Ok(())
+    };
+    let room_status = |room_index: usize| if room_index == goal_index { DroidStatus::MovedToGoal } else { DroidStatus::Moved };
+
+    let mut order: Vec<usize> = (0..map.0.len()).collect();
+    order.sort_by(|a, b| paths[*a].cmp(&paths[*b]));
+
+    // moves taken from the start, and the rooms they led through
+    let mut current: Vec<usize> = Vec::new();
+    let mut rooms = vec![0];
+
+    for target in order {
+        let path = &paths[target];
+        let shared = current.iter().zip(path.iter()).take_while(|(a, b)| a == b).count();
+
+        while current.len() > shared {
+            let dir = MapState::flip(&current.pop().unwrap());
+            rooms.pop();
+            step(dir, room_status(*rooms.last().unwrap()))?;
+        }
+
+        for dir in &path[shared..] {
+            let next = match *map.0[*rooms.last().unwrap()].dir(*dir) {
+                ExploreState::Room(next) => next,
+                _ => { return Err("Path leads through a wall".into()); }
+            };
+            step(*dir, room_status(next))?;
+            current.push(*dir);
+            rooms.push(next);
+        }
+
+        for dir in ALL_DIRS.iter() {
+            if *map.0[target].dir(*dir) == ExploreState::Wall {
+                step(*dir, DroidStatus::Wall)?;
             }
         }
     }
 
-    let part2_answer = part2(&map_state_cell.borrow(), goal_index)?;
+    Ok(())
+}
 
-    Ok((part1_answer, part2_answer))
+#[derive(Debug, PartialEq)]
+struct MapStats {
+    rooms: usize,
+    // rooms with a single neighbour
+    dead_ends: usize,
+    // rooms with three or more neighbours
+    junctions: usize,
+    // most rooms in an unbroken run of rooms with exactly two neighbours
+    longest_corridor: usize
 }
 
-struct QueueEle {
-    room_index: usize,
-    tick: usize
+fn map_stats(map: &MapState) -> MapStats {
+    let adjacent: Vec<Vec<usize>> = map.0.iter().map(|room| room.adjacent()).collect();
+    let is_corridor = |room_index: usize| adjacent[room_index].len() == 2;
+
+    let mut longest_corridor = 0;
+    let mut visited = vec![false; adjacent.len()];
+    for start in 0..adjacent.len() {
+        if visited[start] || !is_corridor(start) {
+            continue;
+        }
+
+        // flood along the corridor; the visited check also stops a
+        // corridor that loops back on itself
+        let mut length = 0;
+        let mut stack = vec![start];
+        visited[start] = true;
+        while let Some(room_index) = stack.pop() {
+            length += 1;
+            for &r in &adjacent[room_index] {
+                if !visited[r] && is_corridor(r) {
+                    visited[r] = true;
+                    stack.push(r);
+                }
+            }
+        }
+        longest_corridor = usize::max(longest_corridor, length);
+    }
+
+    MapStats {
+        rooms: adjacent.len(),
+        dead_ends: adjacent.iter().filter(|rooms| rooms.len() == 1).count(),
+        junctions: adjacent.iter().filter(|rooms| rooms.len() >= 3).count(),
+        longest_corridor
+    }
 }
 
+//
+// Minutes for oxygen to fill the map from the goal room: the distance to
+// the room furthest from it.
+//
 fn part2(map: &MapState, goal_index: usize) -> Result<usize> {
-    let mut queue = VecDeque::new();
-    let mut visited = vec![false; map.0.len()];
-
-    visited[goal_index] = true;
-    queue.push_back(QueueEle {
-        room_index: goal_index,
-        tick: 0
-    });
-
-    let mut ans = 0;
-    while queue.len() > 0 {
-        let top = queue.pop_front().unwrap();
-        let room = map.0.get(top.room_index).ok_or("Invalid index")?;
-        let adj_rooms = room.adjacent();
-
-        for r in adj_rooms {
-            if visited[r] == false {
-                visited[r] = true;
-                queue.push_back(QueueEle {
-                    room_index: r,
-                    tick: top.tick + 1
-                });
-            }
+    map.0.get(goal_index).ok_or("Invalid index")?;
+    let distances = bfs(goal_index, |room_index: &usize| map.0[*room_index].adjacent());
+
+    Ok(distances.values().copied().max().unwrap_or(0) as usize)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // Droid that reports the goal when moving up and an open room otherwise.
+    const GOAL_UP: [i64; 15] = [3,50,1008,50,1,51,1001,51,1,52,4,52,1105,1,0];
+
+    fn two_rooms() -> MapState {
+        let mut map = MapState::new();
+        map.insert_room_and_move(UP_INDEX).unwrap();
+        map
+    }
+
+    #[test]
+    fn test_map_stats() {
+        //  4 3 5
+        //    2
+        //    1
+        //    0
+        //    6
+        let mut map = MapState::new();
+        for dir in [UP_INDEX, UP_INDEX, UP_INDEX, LEFT_INDEX].iter() {
+            map.insert_room_and_move(*dir).unwrap();
         }
-        ans = top.tick;
+        map.1 = 3;
+        map.insert_room_and_move(RIGHT_INDEX).unwrap();
+        map.1 = 0;
+        map.insert_room_and_move(DOWN_INDEX).unwrap();
+
+        assert_eq!(map_stats(&map), MapStats { rooms: 7, dead_ends: 3, junctions: 1, longest_corridor: 3 });
+        assert_eq!(map_stats(&MapState::new()), MapStats { rooms: 1, dead_ends: 0, junctions: 0, longest_corridor: 0 });
+        assert_eq!(map_stats(&two_rooms()), MapStats { rooms: 2, dead_ends: 2, junctions: 0, longest_corridor: 0 });
     }
 
-    Ok(ans)
+    #[test]
+    fn test_droid_status() {
+        assert_eq!(DroidStatus::try_from(0), Ok(DroidStatus::Wall));
+        assert_eq!(DroidStatus::try_from(1), Ok(DroidStatus::Moved));
+        assert_eq!(DroidStatus::try_from(2), Ok(DroidStatus::MovedToGoal));
+        assert_eq!(DroidStatus::try_from(3), Err("Bad output: 3".to_string()));
+        assert!(DroidStatus::try_from(-1).is_err());
+    }
+
+    #[test]
+    fn test_part1_and_2() {
+        // two rooms, the goal just east of the start, and walls everywhere
+        // else: the droid keeps its room in [60] and answers by
+        // 10 * room + direction
+        let program = vec![3,50,1002,60,10,51,1,51,50,51,1008,51,4,52,1005,52,30,1008,51,13,52,1005,52,39,
+                           104,0,1105,1,0,0,1101,1,0,60,104,2,1105,1,0,1101,0,0,60,104,1,1105,1,0];
+        for verify in [false, true] {
+            let explored = part1_and_2(&program, verify).unwrap();
+            assert_eq!((explored.part1, explored.part2, explored.goal_index), (1, 1, 1));
+        }
+    }
+
+    #[test]
+    fn test_verify_map() {
+        verify_map(&GOAL_UP.to_vec(), &two_rooms(), 1).unwrap();
+    }
+
+    #[test]
+    fn test_verify_map_discrepancy() {
+        let mut map = two_rooms();
+        map.1 = 0;
+        map.insert_wall(LEFT_INDEX).unwrap();
+        assert!(verify_map(&GOAL_UP.to_vec(), &map, 1).is_err());
+
+        // the goal is really up, not at the start
+        assert!(verify_map(&GOAL_UP.to_vec(), &two_rooms(), 0).is_err());
+    }
 }
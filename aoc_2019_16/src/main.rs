@@ -1,6 +1,5 @@
 use std::io::{self};
 use std::iter::from_fn;
-use std::iter::Extend;
 
 type Result<T> = ::std::result::Result<T, Box<dyn ::std::error::Error>>;
 
@@ -19,113 +18,186 @@ fn parse_input(input: &str) -> Vec<u32> {
         .filter_map(|x| x.to_digit(10)).collect()
 }
 
+//
+// Runs the phases of the transform, alternating between two buffers so
+// that each phase reuses the memory of the one before last instead of
+// allocating (or cloning) a fresh vector.
+//
 struct FTT {
-    seq: Vec<u8>
+    seq: Vec<u8>,
+    scratch: Vec<u8>
 }
 
-impl Iterator for FTT {
-    type Item = Vec<u8>;
-
-    fn next(&mut self) -> Option<Vec<u8>>{
-        let base_pattern: Vec<i8> = vec![0, 1, 0, -1];
-
-        let mut new_vec = Vec::<u8>::new();
-        for i in 1..=self.seq.len() {
-            let mut base_pattern_index = 0;
-            let mut pattern_count = 0;
-            let mut pattern_iter = from_fn(|| {
-                let to_print = base_pattern[base_pattern_index];
-                pattern_count = pattern_count + 1;
-                if pattern_count == i {
-                    pattern_count = 0;
-                    base_pattern_index = (base_pattern_index + 1) % base_pattern.len();
-                }
-                Some(to_print)
-            });
-
-            let _mul = pattern_iter.next().unwrap(); // drop first value
-
-            let mut val: i32 = 0;
-            for j in &self.seq {
-                let mul = pattern_iter.next().unwrap();
-                val = val + (*j as i32) * (mul as i32);
+impl FTT {
+    fn new(seq: Vec<u8>) -> FTT {
+        let scratch = Vec::with_capacity(seq.len());
+        FTT { seq, scratch }
+    }
+
+    fn step(&mut self) -> &[u8] {
+        apply_phase(&self.seq, &mut self.scratch);
+        std::mem::swap(&mut self.seq, &mut self.scratch);
+        &self.seq
+    }
+}
+
+//
+// One phase computed straight from the definition, walking the full
+// repeating pattern for every output digit, into `out`.
+//
+fn apply_phase(seq: &[u8], out: &mut Vec<u8>) {
+    let base_pattern: Vec<i8> = vec![0, 1, 0, -1];
+
+    out.clear();
+    for i in 1..=seq.len() {
+        let mut base_pattern_index = 0;
+        let mut pattern_count = 0;
+        let mut pattern_iter = from_fn(|| {
+            let to_print = base_pattern[base_pattern_index];
+            pattern_count = pattern_count + 1;
+            if pattern_count == i {
+                pattern_count = 0;
+                base_pattern_index = (base_pattern_index + 1) % base_pattern.len();
             }
-            new_vec.push((i32::abs(val) % 10) as u8);
+            Some(to_print)
+        });
+
+        let _mul = pattern_iter.next().unwrap(); // drop first value
+
+        let mut val: i32 = 0;
+        for j in seq {
+            let mul = pattern_iter.next().unwrap();
+            val = val + (*j as i32) * (mul as i32);
         }
+        out.push((i32::abs(val) % 10) as u8);
+    }
+}
+
+//
+// One phase computed from prefix sums: the pattern for digit j is runs
+// of j ones and minus ones with gaps between, so each run adds or
+// subtracts a whole segment of the input at once.
+//
+fn apply_phase_prefix_sums(new_input: &[u8]) -> Vec<u8> {
+    let mut next_input = Vec::new();
+    let mut prefix_sum: Vec<i64> = Vec::new();
+    prefix_sum.push(new_input[0] as i64);
+    for j in 1..new_input.len() {
+        prefix_sum.push( prefix_sum[j - 1] + new_input[j] as i64 );
+    }
+    for j in 1..=new_input.len() {
+        let mut start = j - 1;
+        let mut sum: i64 = 0;
+        let mut add = true;
+
+        while start < new_input.len() {
+            let segment = if start == 0 {
+                prefix_sum[ usize::min(prefix_sum.len() - 1, start + j - 1) ]
+            } else {
+                prefix_sum[ usize::min(prefix_sum.len() - 1, start + j - 1) ] - prefix_sum[start - 1]
+            };
+
+            if add {
+                sum = sum + segment;
+            } else {
+                sum = sum - segment;
+            }
 
-        self.seq = new_vec.clone();
-        Some(new_vec)
+            start = start + j + j;
+            add = !add;
+        }
+        next_input.push( (i64::abs(sum) % 10) as u8 );
     }
+    next_input
 }
 
 fn part1(input: &str, phases: usize) -> Result<String> {
     let input: Vec<u8> = parse_input(input).into_iter().map(|x| x as u8).collect();
-    let base_pattern = vec![0, 1, 0, -1];
-
-    let ftt_stream = FTT {
-        seq: input.clone()
-    };
+    part1_from_digits(&input, phases, 8)
+}
 
-    let output = ftt_stream.take(phases).last().unwrap();
-    let output_string: String = output.into_iter().take(8).map(|x| std::char::from_digit(x as u32, 10).unwrap() ).collect();
+//
+// Signals given as digit values rather than text must only hold 0 to 9.
+//
+fn check_digits(digits: &[u8]) -> Result<()> {
+    match digits.iter().position(|d| *d > 9) {
+        Some(index) => Err(format!("signal value {} at {} is not a digit", digits[index], index).into()),
+        None => Ok(())
+    }
+}
 
-    Ok(output_string.to_string())
+fn to_string(digits: &[u8]) -> String {
+    digits.iter().map(|x| std::char::from_digit(*x as u32, 10).unwrap()).collect()
 }
 
-fn parse_input_part2(input: &str) -> Vec<u8> {
-    let base_input: Vec<u32> = input.chars().filter_map(|x| x.to_digit(10)).collect();
-    let mut tr: Vec<u32> = Vec::new();
-    for i in 0..10000 {
-        tr.extend(base_input.iter());
+//
+// The first `take` digits after running the phases over the signal.
+//
+fn part1_from_digits(digits: &[u8], phases: usize, take: usize) -> Result<String> {
+    check_digits(digits)?;
+    let mut ftt = FTT::new(digits.to_vec());
+    for _ in 0..phases {
+        ftt.step();
     }
-    tr.into_iter().map(|x| x as u8).collect()
+
+    Ok(to_string(&ftt.seq[..usize::min(take, ftt.seq.len())]))
 }
 
 fn part2(input: &str, phases: usize) -> Result<String> {
-    let mut new_input = parse_input_part2(input);
-    let skip_string: String = new_input.as_slice()[0..7].iter().map(|x| std::char::from_digit(*x as u32, 10).unwrap() ).collect();
-    let skip = skip_string.parse::<usize>()?;
-
-    for i in 1..=phases {
-        let mut next_input = Vec::new();
-        let mut prefix_sum: Vec<i64> = Vec::new();
-        prefix_sum.push(new_input[0] as i64);
-        for j in 1..new_input.len() {
-            prefix_sum.push( prefix_sum[j - 1] + new_input[j] as i64 );
-        }
-        for j in 1..=new_input.len() {
-            let mut start = j - 1;
-            let mut sum: i64 = 0;
-            let mut add = true;
-
-            while start < new_input.len() {
-                let segment = if start == 0 {
-                    prefix_sum[ usize::min(prefix_sum.len() - 1, start + j - 1) ]
-                } else {
-                    prefix_sum[ usize::min(prefix_sum.len() - 1, start + j - 1) ] - prefix_sum[start - 1]
-                };
-
-                if add {
-                    sum = sum + segment;
-                } else {
-                    sum = sum - segment;
-                }
-
-                start = start + j + j;
-                add = !add;
-            }
-            next_input.push( (i64::abs(sum) % 10) as u8 );
-        }
-        new_input = next_input;
+    let digits: Vec<u8> = parse_input(input).into_iter().map(|x| x as u8).collect();
+    part2_from_digits(&digits, phases, 8)
+}
+
+//
+// The `take` digits of the message in the signal repeated 10000 times,
+// found at the offset its first seven digits give.
+//
+fn part2_from_digits(digits: &[u8], phases: usize, take: usize) -> Result<String> {
+    message_at(digits, phases, message_offset(digits)?, take)
+}
+
+//
+// The first seven digits of the repeated signal.
+//
+fn message_offset(digits: &[u8]) -> Result<usize> {
+    check_digits(digits)?;
+    if digits.is_empty() {
+        return Err("signal too short for a message offset".into());
     }
+    Ok(digits.iter().cycle().take(7).fold(0, |offset, x| offset * 10 + *x as usize))
+}
 
-    let output_string: String = new_input.as_slice()[skip..skip+8].into_iter().take(8).map(|x| std::char::from_digit(*x as u32, 10).unwrap() ).collect();
-    Ok(output_string)
+//
+// The `take` digits at `skip` in the signal repeated 10000 times, after
+// running the phases over all of it.
+//
+fn message_at(digits: &[u8], phases: usize, skip: usize, take: usize) -> Result<String> {
+    check_digits(digits)?;
+    let mut new_input = digits.repeat(10000);
+    if skip + take > new_input.len() {
+        return Err("message offset out of range".into());
+    }
+
+    for _ in 0..phases {
+        new_input = apply_phase_prefix_sums(&new_input);
+    }
+
+    Ok(to_string(&new_input[skip..skip + take]))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use proptest::prelude::*;
+
+    //
+    // The message at an offset chosen by the caller rather than read from the
+    // signal, e.g. to try the transform on a signal too short to hold one.
+    //
+    fn part2_with_offset(input: &str, phases: usize, offset: usize) -> Result<String> {
+        let input: Vec<u8> = parse_input(input).into_iter().map(|x| x as u8).collect();
+        message_at(&input, phases, offset, 8)
+    }
 
     #[test]
     fn test_part_1() {
@@ -135,10 +207,81 @@ mod tests {
         assert_eq!(part1("69317163492948606335995924319873", 100).unwrap(), "52432133");
     }
 
+    #[test]
+    fn test_from_digits() {
+        assert_eq!(part1_from_digits(&[1, 2, 3, 4, 5, 6, 7, 8], 4, 8).unwrap(), "01029498");
+        assert_eq!(part1_from_digits(&[1, 2, 3, 4, 5, 6, 7, 8], 4, 3).unwrap(), "010");
+        assert_eq!(part1_from_digits(&[1, 2, 3, 4, 5, 6, 7, 8], 0, 20).unwrap(), "12345678");
+        assert_eq!(part1_from_digits(&[1, 2, 30], 1, 8).unwrap_err().to_string(), "signal value 30 at 2 is not a digit");
+
+        let digits: Vec<u8> = parse_input("03036732577212944063491565474664").into_iter().map(|x| x as u8).collect();
+        assert_eq!(part2_from_digits(&digits, 100, 8).unwrap(), "84462026");
+        assert_eq!(part2_from_digits(&digits, 100, 4).unwrap(), "8446");
+        assert_eq!(part2_from_digits(b"1", 1, 8).unwrap_err().to_string(), "signal value 49 at 0 is not a digit");
+    }
+
+    #[test]
+    fn test_ftt_reuses_buffers() {
+        let mut ftt = FTT::new(vec![1, 2, 3, 4, 5, 6, 7, 8]);
+        let first = ftt.seq.as_ptr();
+
+        assert_eq!(ftt.step(), [4, 8, 2, 2, 6, 1, 5, 8]);
+        let second = ftt.seq.as_ptr();
+        assert_eq!(ftt.step(), [3, 4, 0, 4, 0, 4, 3, 8]);
+        assert_eq!(ftt.seq.as_ptr(), first);
+        assert_eq!(ftt.step(), [0, 3, 4, 1, 5, 5, 1, 8]);
+        assert_eq!(ftt.seq.as_ptr(), second);
+    }
+
     #[test]
     fn test_part_2() {
         assert_eq!(part2("03036732577212944063491565474664", 100).unwrap(), "84462026");
         assert_eq!(part2("02935109699940807407585447034323", 100).unwrap(), "78725270");
         assert_eq!(part2("03081770884921959731165446850517", 100).unwrap(), "53553731");
     }
+
+    #[test]
+    fn test_part_2_with_offset() {
+        // the offsets the samples give themselves
+        assert_eq!(part2_with_offset("03036732577212944063491565474664", 100, 303673).unwrap(), "84462026");
+        assert_eq!(part2_with_offset("02935109699940807407585447034323", 100, 293510).unwrap(), "78725270");
+        assert_eq!(part2_with_offset("03081770884921959731165446850517", 100, 308177).unwrap(), "53553731");
+
+        // in the second half of the signal every digit is the sum of
+        // those from it to the end
+        let mut tail: Vec<u32> = "12345678".repeat(10000)[79990..].chars().map(|c| c.to_digit(10).unwrap()).collect();
+        for _ in 0..2 {
+            for i in (0..tail.len() - 1).rev() {
+                tail[i] = (tail[i] + tail[i + 1]) % 10;
+            }
+        }
+        let expected: String = tail[..8].iter().map(|d| std::char::from_digit(*d, 10).unwrap()).collect();
+        assert_eq!(part2_with_offset("12345678", 2, 79990).unwrap(), expected);
+
+        assert_eq!(part2_with_offset("12345678", 2, 79993).unwrap_err().to_string(), "message offset out of range");
+    }
+
+    proptest! {
+        // the two ways of running a phase must agree from any offset in the
+        // back half of the signal, where part 2 reads its message from
+        #[test]
+        fn phases_agree(signal in prop::collection::vec(0u8..10, 1..64), phases in 1usize..5, at in 0.0f64..1.0) {
+            let offset = signal.len() / 2 + (at * (signal.len() - signal.len() / 2) as f64) as usize;
+
+            let mut naive = FTT::new(signal.clone());
+            let mut prefix_sums = signal;
+            for _ in 0..phases {
+                naive.step();
+                prefix_sums = apply_phase_prefix_sums(&prefix_sums);
+            }
+
+            prop_assert_eq!(&naive.seq[offset..], &prefix_sums[offset..]);
+        }
+    }
+
+    #[test]
+    fn test_part_2_offset_out_of_range() {
+        // offset 1234567, but the expanded signal is only 70000 digits long
+        assert_eq!(part2("1234567", 100).unwrap_err().to_string(), "message offset out of range");
+    }
 }
@@ -1,301 +1,15 @@
 use std::io::{self};
-use std::collections::VecDeque;
 use std::collections::HashSet;
 use std::iter::*;
-use std::cell::RefCell;
+use aoc_util::geom::{self, Direction, Point};
+use intcode::IntCode;
+use log::debug;
 
 type Result<T> = ::std::result::Result<T, Box<dyn ::std::error::Error>>;
 
-#[derive(Debug,PartialEq)]
-enum ParameterType {
-    Ref(usize),
-    Value(i64),
-    Relative(i64)
-}
-
-enum Instruction {
-    Add { left_op: ParameterType, right_op: ParameterType, into: ParameterType },
-    Mul { left_op: ParameterType, right_op: ParameterType, into: ParameterType },
-    Input { into: ParameterType },
-    Output { param: ParameterType },
-    JumpIfTrue { cond: ParameterType, to: ParameterType },
-    JumpIfFalse { cond: ParameterType, to: ParameterType },
-    LessThan { left_op: ParameterType, right_op: ParameterType, into: ParameterType },
-    Equals { left_op: ParameterType, right_op: ParameterType, into: ParameterType },
-    RelativeBase { adjust: ParameterType },
-    Terminate,
-}
-
-struct IntCode<T: Iterator> {
-    memory: Vec<i64>,
-    address_ptr: usize,
-    input_stream: T,
-    output_buffer: VecDeque<i64>,
-    is_terminated: bool,
-    relative_ptr: i64
-}
-
-struct OutputStream<T: Iterator>(IntCode<T>);
-
-impl<T> Iterator for OutputStream<T> where
-    T: Iterator<Item = i64>
-{
-    type Item = i64;
-    fn next(&mut self) -> Option<i64> {
-        if self.0.output_buffer.len() > 0 {
-            self.0.output_buffer.pop_front()
-        } else {
-            self.0.run_to_next_output()
-        }
-    }
-}
-
-impl<T> IntCode<T> where
-    T: Iterator<Item = i64> {
-    fn init(memory: &Vec<i64>, input_stream: T) -> IntCode<T> {
-        IntCode {
-            memory: memory.clone(),
-            address_ptr: 0,
-            input_stream: input_stream,
-            output_buffer: VecDeque::new(),
-            is_terminated: false,
-            relative_ptr: 0
-        }
-    }
-
-    fn parse_op_code(input: &i64) -> Result<(u32, VecDeque<ParameterType>)> {
-        let op_code = input % 100;
-        let mut parameter_mode = VecDeque::<ParameterType>::new();
-        let mut parameter_stream = input / 100;
-
-        while parameter_stream > 0 {
-            parameter_mode.push_back(
-                match parameter_stream % 10 {
-                    0 => ParameterType::Ref(0),
-                    1 => ParameterType::Value(0),
-                    2 => ParameterType::Relative(0),
-                    _ => { return Err(format!("Invalid OpCode: {}", input).into()) }
-                }
-            );
-            parameter_stream /= 10;
-        }
-
-        Ok((op_code as u32, parameter_mode))
-    }
-
-    fn output_stream(self) -> OutputStream<T> {
-        OutputStream(self)
-    }
-
-    fn run_to_next_output(&mut self) -> Option<i64> {
-        while self.output_buffer.len() == 0 && self.is_terminated == false {
-            // bad code; output iterator should be a result
-            self.run_tick().unwrap();
-        }
-
-        self.output_buffer.pop_front()
-    }
-
-    fn read_parameter(
-        &mut self,
-        parameter_mode: &mut VecDeque<ParameterType>,
-        is_writing: bool // If parameter is for a write operation, parameter type must be a reference
-    ) -> Result<ParameterType> {
-        let parameter_value = self.memory.get(self.address_ptr).ok_or("Invalid Address, address pointer out of bounds when reading parameter")?;
-        let parameter_type = parameter_mode.pop_front().unwrap_or(ParameterType::Ref(0));
-
-        self.address_ptr = self.address_ptr + 1;
-
-        match parameter_type {
-            ParameterType::Ref(_) => {
-                Ok(ParameterType::Ref(*parameter_value as usize))
-            },
-            ParameterType::Value(_) => {
-                if is_writing {
-                    Err("Invalid parameter type: parameter is for a write operation".into())
-                } else {
-                    Ok(ParameterType::Value(*parameter_value))
-                }
-            },
-            ParameterType::Relative(_) => {
-                Ok(ParameterType::Relative(*parameter_value))
-            }
-        }
-    }
-
-    fn read_instruction(&mut self) -> Result<(Instruction)> {
-        let op_code = self.memory.get(self.address_ptr).ok_or("Invalid Address, address pointer out of bounds when reading instruction")?;
-        self.address_ptr = self.address_ptr + 1;
-
-        let (op_code, mut parameter_mode) = IntCode::<T>::parse_op_code(op_code)?;
-
-        let instruction = match op_code {
-            1 => {
-                Instruction::Add {
-                    left_op: self.read_parameter(&mut parameter_mode, false)?,
-                    right_op: self.read_parameter(&mut parameter_mode, false)?,
-                    into: self.read_parameter(&mut parameter_mode, true)?
-                }
-            }
-            2 => {
-                Instruction::Mul {
-                    left_op: self.read_parameter(&mut parameter_mode, false)?,
-                    right_op: self.read_parameter(&mut parameter_mode, false)?,
-                    into: self.read_parameter(&mut parameter_mode, true)?
-                }
-            }
-            3 => {
-                Instruction::Input {
-                    into: self.read_parameter(&mut parameter_mode, true)?
-                }
-            },
-            4 => {
-                Instruction::Output {
-                    param: self.read_parameter(&mut parameter_mode, false)?
-                }
-            }
-            5 => {
-                Instruction::JumpIfTrue {
-                    cond: self.read_parameter(&mut parameter_mode, false)?,
-                    to: self.read_parameter(&mut parameter_mode, false)?
-                }
-            }
-            6 => {
-                Instruction::JumpIfFalse {
-                    cond: self.read_parameter(&mut parameter_mode, false)?,
-                    to: self.read_parameter(&mut parameter_mode, false)?
-                }
-            }
-            7 => {
-                Instruction::LessThan {
-                    left_op: self.read_parameter(&mut parameter_mode, false)?,
-                    right_op: self.read_parameter(&mut parameter_mode, false)?,
-                    into: self.read_parameter(&mut parameter_mode, true)?
-                }
-            },
-            8 => {
-                Instruction::Equals {
-                    left_op: self.read_parameter(&mut parameter_mode, false)?,
-                    right_op: self.read_parameter(&mut parameter_mode, false)?,
-                    into: self.read_parameter(&mut parameter_mode, true)?
-                }
-            }
-            9 => {
-                Instruction::RelativeBase {
-                    adjust: self.read_parameter(&mut parameter_mode, false)?
-                }
-            }
-            99 => {
-                Instruction::Terminate
-            }
-            _ => {
-                return Err("Invalid Opcode".into());
-            }
-        };
-
-        Ok(instruction)
-    }
-
-    fn resolve_parameter_value(&self, parameter: ParameterType) -> Result<i64> {
-        match parameter {
-            ParameterType::Ref(address) => {
-                Ok(*self.memory.get(address).unwrap_or(&0))
-            },
-            ParameterType::Value(value) => {
-                Ok(value)
-            },
-            ParameterType::Relative(offset) => {
-                Ok(*self.memory.get((self.relative_ptr + offset) as usize).unwrap_or(&0))
-            }
-        }
-    }
-
-    fn write_memory(&mut self, into: ParameterType, value: i64) -> Result<()> {
-        let address = match into {
-            ParameterType::Ref(address) => {
-                address
-            },
-            ParameterType::Relative(offset) => {
-                (self.relative_ptr + offset) as usize
-            },
-            _ => {
-                panic!("")
-            }
-        };
-
-        if address >= self.memory.len() {
-            self.memory.resize(address + 1, 0);
-        }
-
-        let into_ref = self.memory.get_mut(address).ok_or(format!("Invalid address reference: {}", address))?;
-        *into_ref = value;
-
-        Ok(())
-    }
-
-    fn run_tick(&mut self) -> Result<()> {
-        let instruction = self.read_instruction()?;
-
-        match instruction {
-            Instruction::Add { left_op, right_op, into } => {
-                let sum = self.resolve_parameter_value(left_op)? + self.resolve_parameter_value(right_op)?;
-                self.write_memory(into, sum)?;
-            }
-            Instruction::Mul { left_op, right_op, into } => {
-                let product = self.resolve_parameter_value(left_op)? * self.resolve_parameter_value(right_op)?;
-                self.write_memory(into, product)?;
-            }
-            Instruction::Input { into } => {
-                let input_value = self.input_stream.next().ok_or("Ran out of input")?;
-                self.write_memory(into, input_value)?;
-            }
-            Instruction::Output { param } => {
-                self.output_buffer.push_back(self.resolve_parameter_value(param)?);
-            }
-            Instruction::JumpIfTrue { cond, to } => {
-                let val = self.resolve_parameter_value(cond)?;
-                if val != 0 {
-                    self.address_ptr = self.resolve_parameter_value(to)? as usize;
-                }
-            }
-            Instruction::JumpIfFalse { cond, to } => {
-                let val = self.resolve_parameter_value(cond)?;
-                if val == 0 {
-                    self.address_ptr = self.resolve_parameter_value(to)? as usize;
-                }
-            }
-            Instruction::LessThan { left_op, right_op, into } => {
-                let less_than = if self.resolve_parameter_value(left_op)? < self.resolve_parameter_value(right_op)? {
-                    1
-                } else { 0 };
-                self.write_memory(into, less_than)?;
-            }
-            Instruction::Equals { left_op, right_op, into } => {
-                let equals = if self.resolve_parameter_value(left_op)? == self.resolve_parameter_value(right_op)? {
-                    1
-                } else { 0 };
-                self.write_memory(into, equals)?;
-            }
-            Instruction::RelativeBase { adjust } => {
-                self.relative_ptr = self.relative_ptr + self.resolve_parameter_value(adjust)?;
-            }
-            Instruction::Terminate => {
-                self.is_terminated = true;
-            }
-        };
-
-        Ok(())
-    }
-
-    fn run_to_termination(&mut self) -> Result<()> {
-        while self.is_terminated == false {
-            self.run_tick()?;
-        }
-        Ok(())
-    }
-}
-
 fn main() -> Result<()> {
+    env_logger::init();
+
     let mut input = String::new();
     io::stdin().read_line(&mut input)?;
 
@@ -319,7 +33,6 @@ fn parse_map(input: &Vec<i64>) -> MapType {
     let map_string: String = output.iter().map(|x| (*x as u8) as char).collect();
 
     let mut map: Vec<Vec<char>> = Vec::new();
-    println!("{}", map_string);
     map_string.lines().for_each(|x| {
         let mut map_line = Vec::new();
         if x.trim().len() > 0 {
@@ -333,6 +46,12 @@ fn parse_map(input: &Vec<i64>) -> MapType {
     map
 }
 
+fn print_map(map: &MapType) {
+    for row in map {
+        debug!("{}", row.iter().collect::<String>());
+    }
+}
+
 fn path_to_string(path: &PathType) -> String {
     let mut output = String::new();
     for p in path {
@@ -345,13 +64,6 @@ fn path_to_string(path: &PathType) -> String {
     output
 }
 
-struct Coord(i16, i16);
-
-#[derive(Copy, Clone, Debug)]
-enum Direction {
-    Up, Down, Left, Right
-}
-
 #[derive(Clone, Debug)]
 enum Turn {
     L(Direction),
@@ -381,57 +93,42 @@ impl Turn {
             Turn::R(x) => *x
         }
     }
-}
-
-impl Direction {
-    fn val(&self) -> Coord {
-        match self {
-            Direction::Up => Coord(-1, 0),
-            Direction::Down => Coord(1, 0),
-            Direction::Left => Coord(0, -1),
-            Direction::Right => Coord(0, 1),
-        }
-    }
-    fn turn(&self) -> (Turn, Turn) {
-        match self {
-            Direction::Up => (Turn::L(Direction::Left), Turn::R(Direction::Right)),
-            Direction::Down => (Turn::L(Direction::Right), Turn::R(Direction::Left)),
-            Direction::Left => (Turn::L(Direction::Down), Turn::R(Direction::Up)),
-            Direction::Right => (Turn::L(Direction::Up), Turn::R(Direction::Down))
-        }
+    fn choices(dir: Direction) -> (Turn, Turn) {
+        (Turn::L(dir.left()), Turn::R(dir.right()))
     }
 }
 
-fn has_route(map: &MapType, coord: &Coord) -> bool {
-    let total_row = map.len();
-    let total_col = map[0].len();
-
-    if coord.0 < 0 || coord.0 >= total_row as i16 ||
-        coord.1 < 0 || coord.1 >= total_col as i16
-    {
-        return false;
+fn has_route(map: &MapType, coord: Point) -> bool {
+    match geom::at(map, coord) {
+        Some(tile) => *tile != '.',
+        None => false
     }
-
-    map[coord.0 as usize][coord.1 as usize] != '.'
-}
-
-fn move_in_dir(coord: &Coord, dir: &Direction) -> Coord {
-    let displacement = dir.val();
-    Coord(
-        coord.0 + displacement.0,
-        coord.1 + displacement.1
-    )
 }
 
-fn can_turn(map: &MapType, coord: &Coord, dir: &Direction) -> bool {
-    let new_coord = move_in_dir(coord, dir);
-    has_route(map, &new_coord)
+fn can_turn(map: &MapType, coord: Point, dir: Direction) -> bool {
+    has_route(map, coord.step(dir))
 }
 
 type PathType = Vec<(Turn, usize)>;
 type PathSlice = [(Turn, usize)];
 
-fn feasible(path_slice: &PathSlice) -> bool {
+//
+// Longest the robot accepts, in characters and not counting the newline,
+// for each movement function and for the main routine.
+//
+#[derive(Clone, Copy, Debug)]
+struct RoutineLimits {
+    func_len: usize,
+    main_len: usize
+}
+
+impl Default for RoutineLimits {
+    fn default() -> RoutineLimits {
+        RoutineLimits { func_len: 20, main_len: 20 }
+    }
+}
+
+fn feasible(path_slice: &PathSlice, func_len: usize) -> bool {
     let mut req_size = 0;
     for p in path_slice {
         req_size = req_size + if p.1 >= 10 {
@@ -443,10 +140,10 @@ fn feasible(path_slice: &PathSlice) -> bool {
     }
     req_size -= 1;
 
-    req_size <= 20
+    req_size <= func_len
 }
 
-fn try_split_path(path: &PathType, part_a: &PathSlice, part_b: &PathSlice, part_c: &PathSlice) -> Option<Vec<char>> {
+fn try_split_path(path: &PathType, part_a: &PathSlice, part_b: &PathSlice, part_c: &PathSlice, main_len: usize) -> Option<Vec<char>> {
     let mut start = 0;
     let mut arrangement = Vec::new();
 
@@ -465,7 +162,7 @@ fn try_split_path(path: &PathType, part_a: &PathSlice, part_b: &PathSlice, part_
         }
     }
 
-    if arrangement.len() * 2 - 1 > 20 {
+    if arrangement.len() * 2 - 1 > main_len {
         None
     } else {
         Some(arrangement)
@@ -486,20 +183,20 @@ fn can_consume(path: &PathType, part: &PathSlice, start_index: usize) -> bool {
     return true;
 }
 
-fn break_path(path: &PathType) -> Option<(PathType, PathType, PathType, Vec<char>)> {
+fn break_path(path: &PathType, limits: RoutineLimits) -> Option<(PathType, PathType, PathType, Vec<char>)> {
     let mut split_0 = 0;
     let mut split_1 = 0;
 
     for i in 1..path.len() {
         let part_a = path.get(0..i).unwrap();
-        if !feasible(part_a) {
+        if !feasible(part_a, limits.func_len) {
             break;
         }
 
         for j in (i + 1)..path.len() {
             let part_b = path.get(i..j).unwrap();
 
-            if !feasible(part_b) {
+            if !feasible(part_b, limits.func_len) {
                 break;
             }
 
@@ -516,11 +213,11 @@ fn break_path(path: &PathType) -> Option<(PathType, PathType, PathType, Vec<char
 
             for l in k + 1..path.len() {
                 let part_c = path.get(k..l).unwrap();
-                if !feasible(part_c) {
+                if !feasible(part_c, limits.func_len) {
                     break;
                 }
 
-                let attempt = try_split_path(path, part_a, part_b, part_c);
+                let attempt = try_split_path(path, part_a, part_b, part_c, limits.main_len);
                 match attempt {
                     Some(arrangement) => {
                         return Some(
@@ -572,18 +269,18 @@ fn part2(input: &Vec<i64>) -> Result<i64> {
     //
 
     let mut cur_dir = Direction::Up;
-    let mut cur_coord = Coord(cur_row as i16, cur_col as i16);
+    let mut cur_coord = Point::new(cur_col as i32, cur_row as i32);
     let mut path = Vec::new();
 
     loop {
         //
         // Find next direction
         //
-        let turns = cur_dir.turn();
+        let turns = Turn::choices(cur_dir);
         let mut current_turn = Turn::L(Direction::Up);
-        if can_turn(&map, &cur_coord, &(turns.0).dir()) {
+        if can_turn(&map, cur_coord, (turns.0).dir()) {
             current_turn = turns.0;
-        } else if can_turn(&map, &cur_coord, &(turns.1).dir()) {
+        } else if can_turn(&map, cur_coord, (turns.1).dir()) {
             current_turn = turns.1;
         } else {
             // We are done!
@@ -597,8 +294,8 @@ fn part2(input: &Vec<i64>) -> Result<i64> {
         //
         let mut move_count = 0;
         loop {
-            let next_coord = move_in_dir(&cur_coord, &cur_dir);
-            if !has_route(&map, &next_coord) {
+            let next_coord = cur_coord.step(cur_dir);
+            if !has_route(&map, next_coord) {
                 break;
             } else {
                 move_count = move_count + 1;
@@ -609,8 +306,27 @@ fn part2(input: &Vec<i64>) -> Result<i64> {
         path.push((current_turn, move_count));
     }
 
-    let (path_a, path_b, path_c, arrangement) = break_path(&path).ok_or("cannot find path")?;
-    println!("{}", path_to_string(&path));
+    debug!("{}", path_to_string(&path));
+    let output = encode_routine(&path, RoutineLimits::default())?;
+    debug!("{}", output);
+    if !simulate_routine(&map, &output)? {
+        return Err("routine doesn't cover the scaffold".into());
+    }
+
+    // waking the robot up: address 0 set to 2
+    let input_stream = output.chars().map(|x| x as i64);
+    let machine = IntCode::init_with_patches(input, &[(0, 2)], input_stream)?;
+    let output = machine.output_stream();
+    Ok(output.last().ok_or("No output")?)
+}
+
+//
+// Splits the path into three movement functions and encodes the full
+// command sent to the robot: main routine, functions A, B and C, and
+// the answer to the continuous video feed prompt.
+//
+fn encode_routine(path: &PathType, limits: RoutineLimits) -> Result<String> {
+    let (path_a, path_b, path_c, arrangement) = break_path(path, limits).ok_or("cannot find path")?;
     let mut output = String::new();
     for a in arrangement {
         if output.len() > 0 {
@@ -623,27 +339,102 @@ fn part2(input: &Vec<i64>) -> Result<i64> {
     output = output + &path_to_string(&path_b) + "\n";
     output = output + &path_to_string(&path_c) + "\n";
     output = output + "n\n";
-    println!("{}", output);
+    Ok(output)
+}
 
-    let mut hack = input.clone();
-    hack[0] = 2;
-    let input_stream = output.chars().map(|x| x as i64);
+fn robot_direction(tile: char) -> Option<Direction> {
+    match tile {
+        '^' => Some(Direction::Up),
+        'v' => Some(Direction::Down),
+        '<' => Some(Direction::Left),
+        '>' => Some(Direction::Right),
+        _ => None
+    }
+}
 
-    let machine = IntCode::init(&hack, input_stream);
-    let output = machine.output_stream();
-    Ok(output.last().ok_or("No output")?)
+//
+// Walks the robot through `routine`, the command string sent to it in
+// part 2, without involving the VM: the main routine on the first line,
+// then functions A, B and C (any further line, like the video feed
+// answer, is ignored). Gives whether the robot stays on the scaffold and
+// visits every scaffold tile; a routine that can't be parsed, or a map
+// without the robot, is an error.
+//
+fn simulate_routine(map: &MapType, routine: &str) -> Result<bool> {
+    let lines: Vec<&str> = routine.lines().collect();
+    if lines.len() < 4 {
+        return Err("routine needs a main routine and three functions".into());
+    }
+
+    let mut functions = Vec::new();
+    for (name, line) in ['A', 'B', 'C'].iter().zip(&lines[1..4]) {
+        let tokens: Vec<&str> = line.split(',').collect();
+        if !tokens.len().is_multiple_of(2) {
+            return Err(format!("function {} ends on a turn", name).into());
+        }
+        let mut moves = Vec::new();
+        for step in tokens.chunks(2) {
+            let left = match step[0] {
+                "L" => true,
+                "R" => false,
+                turn => return Err(format!("bad turn {} in function {}", turn, name).into())
+            };
+            let distance: usize = step[1].parse()
+                .map_err(|e| format!("bad distance {} in function {}: {}", step[1], name, e))?;
+            moves.push((left, distance));
+        }
+        functions.push(moves);
+    }
+
+    let (mut position, mut dir) = map.iter().enumerate()
+        .flat_map(|(r, row)| row.iter().enumerate().map(move |(c, tile)| (Point::new(c as i32, r as i32), *tile)))
+        .find_map(|(point, tile)| robot_direction(tile).map(|dir| (point, dir)))
+        .ok_or("Cannot find starting position!")?;
+
+    let mut visited = HashSet::new();
+    visited.insert(position);
+    for call in lines[0].split(',') {
+        let moves = match call {
+            "A" => &functions[0],
+            "B" => &functions[1],
+            "C" => &functions[2],
+            _ => return Err(format!("unknown function {} in main routine", call).into())
+        };
+        for (left, distance) in moves {
+            dir = if *left { dir.left() } else { dir.right() };
+            for _ in 0..*distance {
+                position = position.step(dir);
+                if !has_route(map, position) {
+                    return Ok(false);
+                }
+                visited.insert(position);
+            }
+        }
+    }
+
+    let covered = map.iter().enumerate().all(|(r, row)| {
+        row.iter().enumerate().all(|(c, tile)| *tile != '#' || visited.contains(&Point::new(c as i32, r as i32)))
+    });
+    Ok(covered)
 }
 
-fn part1(input: &Vec<i64>) -> Result<i64> {
-    let map = parse_map(input);
-    let total_row = map.len();
-    let total_col = map[0].len();
+//
+// Sum of row times column over every scaffold intersection. Only cells
+// with a neighbour on all four sides can be one, so a map less than three
+// cells across either way simply has none; an empty map is an error.
+//
+fn alignment_sum(map: &MapType) -> Result<i64> {
+    if map.iter().all(|row| row.is_empty()) {
+        return Err("empty scaffold map".into());
+    }
+
+    let is_scaffold = |r: usize, c: usize| map.get(r).and_then(|row| row.get(c)) == Some(&'#');
 
     let mut sum = 0;
-    for r in 1..total_row-1 {
-        for c in 1..total_col-1 {
-            if map[r][c] == '#' && map[r-1][c] == '#' && map[r+1][c] == '#'
-                && map[r][c-1] == '#' && map[r][c+1] == '#' {
+    for (r, row) in map.iter().enumerate().skip(1).take(map.len().saturating_sub(2)) {
+        for c in 1..row.len().saturating_sub(1) {
+            if is_scaffold(r, c) && is_scaffold(r-1, c) && is_scaffold(r+1, c)
+                && is_scaffold(r, c-1) && is_scaffold(r, c+1) {
                     sum = sum + ((r as i64) * (c as i64));
                 }
         }
@@ -652,3 +443,110 @@ fn part1(input: &Vec<i64>) -> Result<i64> {
     Ok(sum)
 }
 
+fn part1(input: &Vec<i64>) -> Result<i64> {
+    let map = parse_map(input);
+    print_map(&map);
+    alignment_sum(&map)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn to_map(map_string: &str) -> MapType {
+        map_string.lines().map(|line| line.chars().collect()).collect()
+    }
+
+    #[test]
+    fn test_alignment_sum() {
+        let map = to_map("..#..........
+..#..........
+#######...###
+#.#...#...#.#
+#############
+..#...#...#..
+..#####...^..");
+        assert_eq!(alignment_sum(&map).unwrap(), 76);
+    }
+
+    #[test]
+    fn test_alignment_sum_small_maps() {
+        assert_eq!(alignment_sum(&to_map("#####")).unwrap(), 0);
+        assert_eq!(alignment_sum(&to_map("#\n#\n#\n#")).unwrap(), 0);
+        // ragged rows are treated as open space past their end
+        assert_eq!(alignment_sum(&to_map(".#.\n###\n.")).unwrap(), 0);
+
+        assert_eq!(alignment_sum(&to_map("")).unwrap_err().to_string(), "empty scaffold map");
+        assert!(alignment_sum(&vec![vec![]]).is_err());
+    }
+
+    #[test]
+    fn test_simulate_routine() {
+        let map = to_map("#######...#####
+#.....#...#...#
+#.....#...#...#
+......#...#...#
+......#...###.#
+......#.....#.#
+^########...#.#
+......#.#...#.#
+......#########
+........#...#..
+....#########..
+....#...#......
+....#...#......
+....#...#......
+....#####......");
+        let routine = "A,B,C,B,A,C\nR,8,R,8\nR,4,R,4,R,8\nL,6,L,2\nn\n";
+        assert!(simulate_routine(&map, routine).unwrap());
+
+        // stopping early leaves the last stretch unvisited
+        assert!(!simulate_routine(&map, "A,B,C,B,A\nR,8,R,8\nR,4,R,4,R,8\nL,6,L,2\n").unwrap());
+        // a wrong turn at the start walks straight off the scaffold
+        assert!(!simulate_routine(&map, "A,B,C,B,A,C\nL,8,R,8\nR,4,R,4,R,8\nL,6,L,2\n").unwrap());
+
+        assert!(simulate_routine(&map, "A,B,C\nR,8").is_err());
+        assert!(simulate_routine(&map, "A,D\nR,8\nR,4\nL,6").is_err());
+        assert!(simulate_routine(&map, "A\nR,8,L\nR,4\nL,6").is_err());
+        assert!(simulate_routine(&map, "A\nU,8\nR,4\nL,6").is_err());
+        assert!(simulate_routine(&to_map("###"), routine).is_err());
+    }
+
+    fn to_path(path_string: &str) -> PathType {
+        let tokens: Vec<&str> = path_string.split(',').collect();
+        tokens.chunks(2).map(|step| {
+            let turn = match step[0] {
+                "L" => Turn::L(Direction::Up),
+                _ => Turn::R(Direction::Up)
+            };
+            (turn, step[1].parse().unwrap())
+        }).collect()
+    }
+
+    #[test]
+    fn test_encode_routine() {
+        let path = to_path("R,8,R,8,R,4,R,4,R,8,L,6,L,2,R,4,R,4,R,8,R,8,R,8,L,6,L,2");
+        assert_eq!(encode_routine(&path, RoutineLimits::default()).unwrap(),
+                   "A,B,B,C,B,B,A,C\nR,8,R,8\nR,4\nR,8,L,6,L,2\nn\n");
+    }
+
+    #[test]
+    fn test_encode_routine_no_split() {
+        let path = to_path("R,1,R,2,R,3,R,4,R,5,R,6,R,7");
+        assert!(encode_routine(&path, RoutineLimits::default()).is_err());
+    }
+
+    #[test]
+    fn test_routine_limits() {
+        let path = to_path("R,8,R,8,R,4,R,4,R,8,L,6,L,2,R,4,R,4,R,8,R,8,R,8,L,6,L,2");
+
+        // the default split's main routine is 15 characters long
+        let short_main = RoutineLimits { main_len: 11, ..RoutineLimits::default() };
+        assert_eq!(encode_routine(&path, short_main).unwrap(),
+                   "A,B,C,B,A,C\nR,8,R,8\nR,4,R,4\nR,8,L,6,L,2\nn\n");
+
+        // R,8,L,6,L,2 can't be broken up
+        let short_funcs = RoutineLimits { func_len: 7, ..RoutineLimits::default() };
+        assert!(encode_routine(&path, short_funcs).is_err());
+    }
+}
@@ -13,6 +13,7 @@ enum ParameterType {
     Relative(i64)
 }
 
+#[derive(Debug)]
 enum Instruction {
     Add { left_op: ParameterType, right_op: ParameterType, into: ParameterType },
     Mul { left_op: ParameterType, right_op: ParameterType, into: ParameterType },
@@ -3,6 +3,7 @@ use std::collections::VecDeque;
 use std::collections::HashSet;
 use std::iter::*;
 use std::collections::HashMap;
+use log::{debug, trace};
 
 type Result<T> = ::std::result::Result<T, Box<dyn ::std::error::Error>>;
 
@@ -13,6 +14,7 @@ enum ParameterType {
     Relative(i64)
 }
 
+#[derive(Debug)]
 enum Instruction {
     Add { left_op: ParameterType, right_op: ParameterType, into: ParameterType },
     Mul { left_op: ParameterType, right_op: ParameterType, into: ParameterType },
@@ -296,6 +298,8 @@ impl<T> IntCode<T> where
 }
 
 fn main() -> Result<()> {
+    env_logger::init();
+
     let mut input = String::new();
     io::stdin().read_line(&mut input)?;
 
@@ -311,8 +315,26 @@ fn main() -> Result<()> {
     Ok(())
 }
 
+const WALK_SCRIPT: &str = "NOT A J\nNOT C T\nOR T J\nAND D J\nWALK\n";
+
+const RUN_SCRIPT: &str = "NOT H T
+OR I T
+AND A T
+NOT H J
+OR G J
+AND F J
+OR J T
+OR C J
+AND B J
+AND E T
+OR T J
+AND A J
+NOT J J
+AND D J
+RUN\n";
+
 fn part1(input: &Vec<i64>) -> Result<i64> {
-    let output = "NOT A J\nNOT C T\nOR T J\nAND D J\nWALK\n";
+    let output = WALK_SCRIPT;
     let input_stream = output.chars().map(|x| x as i64);
     let machine = IntCode::init(&input, input_stream);
     let output: Vec<i64> = machine.output_stream().collect();
@@ -417,6 +439,55 @@ fn should_jump_sim(holes: &Vec<bool>) -> (usize, usize, bool) {
     }
 }
 
+//
+// Runs a springscript program against the sensors, where holes[0] is A
+// (true meaning ground) and so on, and returns whether the droid jumps.
+// Instructions after WALK or RUN are ignored; with WALK only A to D can be
+// read, like on the real droid.
+//
+fn simulate_springscript(script: &str, holes: &[bool]) -> Result<bool> {
+    let mut t = false;
+    let mut j = false;
+    let sensors = if script.lines().any(|line| line.trim() == "WALK") { 4 } else { 9 };
+
+    for (index, line) in script.lines().map(|line| line.trim()).enumerate().filter(|(_, line)| !line.is_empty()) {
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        match tokens[..] {
+            ["WALK"] | ["RUN"] => break,
+            [op, x, y] => {
+                let x = match x {
+                    "T" => t,
+                    "J" => j,
+                    _ => {
+                        let sensor = match x.as_bytes() {
+                            [c @ b'A'..=b'I'] => (c - b'A') as usize,
+                            _ => return Err(format!("line {} ({:?}): unknown register {}", index + 1, line, x).into())
+                        };
+                        if sensor >= sensors || sensor >= holes.len() {
+                            return Err(format!("line {} ({:?}): sensor {} is out of range", index + 1, line, x).into());
+                        }
+                        holes[sensor]
+                    }
+                };
+                let y = match y {
+                    "T" => &mut t,
+                    "J" => &mut j,
+                    _ => return Err(format!("line {} ({:?}): {} is not writable", index + 1, line, y).into())
+                };
+                *y = match op {
+                    "AND" => x && *y,
+                    "OR" => x || *y,
+                    "NOT" => !x,
+                    _ => return Err(format!("line {} ({:?}): unknown instruction {}", index + 1, line, op).into())
+                };
+            },
+            _ => return Err(format!("line {} ({:?}): unknown instruction", index + 1, line).into())
+        }
+    }
+
+    Ok(j)
+}
+
 #[derive(Debug,PartialEq,Clone,Copy)]
 enum ComplementField {
     True,
@@ -521,7 +592,10 @@ fn part2(input: &Vec<i64>) -> Result<i64> {
     for i in 0..N {
         let holes = convert_to_hole(&i);
         let jump = should_jump(&holes);
-        println!("{} {:?} {}", i, holes, jump);
+        trace!("{} {:?} {}", i, holes, jump);
+        if simulate_springscript(RUN_SCRIPT, &holes)? != jump {
+            return Err(format!("springscript disagrees with should_jump on {:?}", holes).into());
+        }
         if jump {
             minterms.push(i);
             let complement: Vec<ComplementField> = holes.iter().map(|x| match x { true => ComplementField::True, false => ComplementField::False }).collect();
@@ -588,30 +662,16 @@ fn part2(input: &Vec<i64>) -> Result<i64> {
                 term = term + &cur.to_string() + "'";
             }
         }
-        println!("{:?} {:?}", p.0, p.1);
-        println!("{}", term);
+        debug!("{:?} {:?}", p.0, p.1);
+        debug!("{}", term);
     }
 
-    let output = "NOT H T
-OR I T
-AND A T
-NOT H J
-OR G J
-AND F J
-OR J T
-OR C J
-AND B J
-AND E T
-OR T J
-AND A J
-NOT J J
-AND D J
-RUN\n";
+    let output = RUN_SCRIPT;
     let input_stream = output.chars().map(|x| x as i64);
     let machine = IntCode::init(&input, input_stream);
     let output: Vec<i64> = machine.output_stream().collect();
     let output_string: String = output.iter().map(|x| (*x as u8) as char).collect();
-    println!("{}", output_string);
+    debug!("{}", output_string);
     Ok(output[output.len() - 1])
 //    Ok(1)
 }
@@ -629,6 +689,35 @@ mod test {
         assert_eq!(should_jump(&vec![true, true, true, true, false, false, true, false, false]), false);
         assert_eq!(should_jump(&vec![true, true, true, true, false, false, true, false, true]), false);
     }
+
+    #[test]
+    fn test_simulate_springscript() {
+        // jump if there is a hole at A or C and ground at D
+        let holes = [true, true, false, true, true, true, true, true, true];
+        assert!(simulate_springscript(WALK_SCRIPT, &holes).unwrap());
+        let holes = [true, true, true, false, true, true, true, true, true];
+        assert!(!simulate_springscript(WALK_SCRIPT, &holes).unwrap());
+
+        let holes = [true; 9];
+        assert_eq!(simulate_springscript("NOT E T\nWALK\n", &holes).unwrap_err().to_string(),
+                   "line 1 (\"NOT E T\"): sensor E is out of range");
+        assert_eq!(simulate_springscript("NOT X J\nRUN\n", &holes).unwrap_err().to_string(),
+                   "line 1 (\"NOT X J\"): unknown register X");
+        assert_eq!(simulate_springscript("NOT A D\nRUN\n", &holes).unwrap_err().to_string(),
+                   "line 1 (\"NOT A D\"): D is not writable");
+        assert_eq!(simulate_springscript("XOR A J\nRUN\n", &holes).unwrap_err().to_string(),
+                   "line 1 (\"XOR A J\"): unknown instruction XOR");
+        assert_eq!(simulate_springscript("\nJUMP\n", &holes).unwrap_err().to_string(),
+                   "line 2 (\"JUMP\"): unknown instruction");
+    }
+
+    #[test]
+    fn test_run_script_matches_should_jump() {
+        for mask in 0..(1 << 9) {
+            let holes = convert_to_hole(&mask);
+            assert_eq!(simulate_springscript(RUN_SCRIPT, &holes).unwrap(), should_jump(&holes), "{:?}", holes);
+        }
+    }
 }
 /*
 .................
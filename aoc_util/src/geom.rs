@@ -0,0 +1,118 @@
+//!
+//! Grid geometry. Every day uses screen coordinates: `x` is the column and
+//! grows to the right, `y` is the row and grows downwards, so `Up` is `y - 1`.
+//!
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Point {
+    pub x: i32,
+    pub y: i32
+}
+
+impl Point {
+    pub fn new(x: i32, y: i32) -> Point {
+        Point { x, y }
+    }
+
+    pub fn origin() -> Point {
+        Point::new(0, 0)
+    }
+
+    pub fn step(self, dir: Direction) -> Point {
+        let delta = dir.value();
+        Point::new(self.x + delta.x, self.y + delta.y)
+    }
+
+    pub fn manhattan(self) -> i32 {
+        self.x.abs() + self.y.abs()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Direction {
+    Up, Right, Down, Left
+}
+
+impl Direction {
+    pub const ALL: [Direction; 4] = [Direction::Up, Direction::Right, Direction::Down, Direction::Left];
+
+    pub fn value(self) -> Point {
+        match self {
+            Direction::Up => Point::new(0, -1),
+            Direction::Right => Point::new(1, 0),
+            Direction::Down => Point::new(0, 1),
+            Direction::Left => Point::new(-1, 0)
+        }
+    }
+
+    fn index(self) -> usize {
+        match self {
+            Direction::Up => 0,
+            Direction::Right => 1,
+            Direction::Down => 2,
+            Direction::Left => 3
+        }
+    }
+
+    pub fn left(self) -> Direction {
+        Direction::ALL[(self.index() + 3) % 4]
+    }
+
+    pub fn right(self) -> Direction {
+        Direction::ALL[(self.index() + 1) % 4]
+    }
+
+    pub fn opposite(self) -> Direction {
+        Direction::ALL[(self.index() + 2) % 4]
+    }
+}
+
+///
+/// Bounds-checked lookup into a row-major grid; `None` for any point
+/// outside of it, including negative coordinates.
+///
+pub fn at<T>(grid: &[Vec<T>], point: Point) -> Option<&T> {
+    if point.x < 0 || point.y < 0 {
+        return None;
+    }
+    grid.get(point.y as usize)?.get(point.x as usize)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_turns() {
+        assert_eq!(Direction::Up.left(), Direction::Left);
+        assert_eq!(Direction::Up.right(), Direction::Right);
+        assert_eq!(Direction::Left.left(), Direction::Down);
+        assert_eq!(Direction::Down.right(), Direction::Left);
+        assert_eq!(Direction::Right.opposite(), Direction::Left);
+
+        for dir in Direction::ALL.iter() {
+            assert_eq!(dir.left().right(), *dir);
+            assert_eq!(dir.left().left(), dir.opposite());
+        }
+    }
+
+    #[test]
+    fn test_step() {
+        let p = Point::new(2, 3);
+        assert_eq!(p.step(Direction::Up), Point::new(2, 2));
+        assert_eq!(p.step(Direction::Down), Point::new(2, 4));
+        assert_eq!(p.step(Direction::Left), Point::new(1, 3));
+        assert_eq!(p.step(Direction::Right), Point::new(3, 3));
+        assert_eq!(Point::new(-3, 4).manhattan(), 7);
+    }
+
+    #[test]
+    fn test_at() {
+        let grid = vec![vec!['a', 'b'], vec!['c', 'd']];
+        assert_eq!(at(&grid, Point::new(1, 0)), Some(&'b'));
+        assert_eq!(at(&grid, Point::new(0, 1)), Some(&'c'));
+        assert_eq!(at(&grid, Point::new(2, 0)), None);
+        assert_eq!(at(&grid, Point::new(0, 2)), None);
+        assert_eq!(at(&grid, Point::new(-1, 0)), None);
+    }
+}
@@ -0,0 +1,7 @@
+//!
+//! Helpers shared between the daily solutions.
+//!
+
+pub mod geom;
+pub mod permutations;
+pub mod search;
@@ -0,0 +1,97 @@
+//!
+//! Every ordering of a handful of items, for puzzles small enough to
+//! simply try them all.
+//!
+
+///
+/// Iterator over the permutations of some items, each yielded as its own
+/// Vec. Orderings are produced with the iterative form of Heap's
+/// algorithm, one swap apart, starting with the items as given.
+///
+/// ```
+/// use aoc_util::permutations::Permutations;
+///
+/// let orderings: Vec<Vec<u32>> = Permutations::new(1..=3).collect();
+/// assert_eq!(orderings, [[1, 2, 3], [2, 1, 3], [3, 1, 2], [1, 3, 2], [2, 3, 1], [3, 2, 1]]);
+/// ```
+///
+pub struct Permutations<T> {
+    items: Vec<T>,
+    counters: Vec<usize>,
+    i: usize,
+    started: bool
+}
+
+impl<T: Clone> Permutations<T> {
+    pub fn new<I: IntoIterator<Item = T>>(items: I) -> Permutations<T> {
+        let items: Vec<T> = items.into_iter().collect();
+        Permutations { counters: vec![0; items.len()], items, i: 1, started: false }
+    }
+}
+
+impl<T: Clone> Iterator for Permutations<T> {
+    type Item = Vec<T>;
+
+    fn next(&mut self) -> Option<Vec<T>> {
+        if !self.started {
+            self.started = true;
+            return Some(self.items.clone());
+        }
+
+        while self.i < self.items.len() {
+            let i = self.i;
+            if self.counters[i] < i {
+                if i.is_multiple_of(2) {
+                    self.items.swap(0, i);
+                } else {
+                    self.items.swap(self.counters[i], i);
+                }
+                self.counters[i] += 1;
+                self.i = 1;
+                return Some(self.items.clone());
+            }
+            self.counters[i] = 0;
+            self.i += 1;
+        }
+
+        None
+    }
+}
+
+///
+/// Every ordering of a slice, as `Permutations` gives them.
+///
+/// ```
+/// use aoc_util::permutations::permutations;
+///
+/// assert_eq!(permutations(&["a", "b"]).collect::<Vec<_>>(), [["a", "b"], ["b", "a"]]);
+/// ```
+///
+pub fn permutations<T: Clone>(items: &[T]) -> impl Iterator<Item = Vec<T>> {
+    Permutations::new(items.iter().cloned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_permutations() {
+        let seen: HashSet<Vec<usize>> = Permutations::new(0..5).collect();
+        assert_eq!(Permutations::new(0..5).count(), 120);
+        assert_eq!(seen.len(), 120);
+        assert!(seen.iter().all(|p| {
+            let mut sorted = p.clone();
+            sorted.sort();
+            sorted == [0, 1, 2, 3, 4]
+        }));
+
+        let orderings: Vec<Vec<char>> = permutations(&['a', 'b', 'c', 'd', 'e']).collect();
+        let distinct: HashSet<&Vec<char>> = orderings.iter().collect();
+        assert_eq!((orderings.len(), distinct.len()), (120, 120));
+
+        assert_eq!(Permutations::new(vec!['a']).collect::<Vec<_>>(), [['a']]);
+        assert_eq!(Permutations::new(Vec::<u8>::new()).count(), 1);
+    }
+}
@@ -0,0 +1,66 @@
+//!
+//! Graph searches over nodes that are only known through a function
+//! listing their neighbours, so the same code walks a maze, an adjacency
+//! list or anything else.
+//!
+
+use std::collections::{HashMap, VecDeque};
+use std::hash::Hash;
+
+///
+/// Breadth first search from `start`, returning the distance in steps to
+/// every node reachable from it, `start` itself included at 0.
+///
+/// ```
+/// use aoc_util::search::bfs;
+///
+/// // 0 - 1 - 2, with 3 off on its own
+/// let distances = bfs(0, |&n: &u32| if n < 2 { vec![n + 1] } else { vec![] });
+/// assert_eq!(distances[&2], 2);
+/// assert!(!distances.contains_key(&3));
+/// ```
+///
+pub fn bfs<N, I, F>(start: N, neighbors: F) -> HashMap<N, u32> where
+    N: Clone + Eq + Hash,
+    I: IntoIterator<Item = N>,
+    F: Fn(&N) -> I
+{
+    let mut distances = HashMap::new();
+    let mut queue = VecDeque::new();
+
+    distances.insert(start.clone(), 0);
+    queue.push_back((start, 0));
+
+    while let Some((node, distance)) = queue.pop_front() {
+        for next in neighbors(&node) {
+            if !distances.contains_key(&next) {
+                distances.insert(next.clone(), distance + 1);
+                queue.push_back((next, distance + 1));
+            }
+        }
+    }
+
+    distances
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bfs() {
+        // a square 0-1-2-3-0 with a tail 2-4-5, and 6 unreachable
+        let edges = [(0, 1), (1, 2), (2, 3), (3, 0), (2, 4), (4, 5), (6, 6)];
+        let neighbors = |n: &u32| -> Vec<u32> {
+            edges.iter()
+                .filter_map(|&(a, b)| if a == *n { Some(b) } else if b == *n { Some(a) } else { None })
+                .collect()
+        };
+
+        let distances = bfs(0, neighbors);
+        let expected: HashMap<u32, u32> = [(0, 0), (1, 1), (3, 1), (2, 2), (4, 3), (5, 4)].iter().cloned().collect();
+        assert_eq!(distances, expected);
+
+        assert_eq!(bfs(6, neighbors), [(6, 0)].iter().cloned().collect());
+    }
+}
@@ -0,0 +1,47 @@
+//!
+//! Interpreter throughput, reported in instructions per second:
+//!
+//!     cargo bench --features bench --bench intcode
+//!
+
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+
+use intcode::IntCode;
+
+// Counts [13] up to 100000; three instructions per iteration.
+const COUNTING_LOOP: [i64; 15] = [1001,13,1,13,1007,13,100_000,14,1005,14,0,99,0,0,0];
+
+// Day 9 sample that prints a copy of itself, exercising the relative base.
+const QUINE: [i64; 16] = [109,1,204,-1,1001,100,1,100,1008,100,16,101,1006,101,0,99];
+
+fn instruction_count(program: &[i64]) -> u64 {
+    let mut machine = IntCode::init(program, ::std::iter::empty());
+    let mut ticks = 0;
+    while !machine.is_terminated() {
+        machine.run_tick().unwrap();
+        ticks += 1;
+    }
+    ticks
+}
+
+fn bench_program(c: &mut Criterion, name: &str, program: &[i64]) {
+    let mut group = c.benchmark_group(name);
+    group.throughput(Throughput::Elements(instruction_count(program)));
+    group.bench_function("run_to_termination", |b| b.iter(|| {
+        let mut machine = IntCode::init(program, ::std::iter::empty());
+        machine.run_to_termination().unwrap();
+        machine
+    }));
+    group.bench_function("output_stream", |b| b.iter(|| {
+        IntCode::init(program, ::std::iter::empty()).output_stream().count()
+    }));
+    group.finish();
+}
+
+fn interpreter(c: &mut Criterion) {
+    bench_program(c, "counting_loop", &COUNTING_LOOP);
+    bench_program(c, "quine", &QUINE);
+}
+
+criterion_group!(benches, interpreter);
+criterion_main!(benches);
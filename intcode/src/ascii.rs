@@ -0,0 +1,121 @@
+use crate::{HaltReason, InputQueue, IntCode, Result};
+
+///
+/// Output collected between two prompts. Bytes are decoded as UTF-8 once
+/// the text is complete, so multi-byte characters may span outputs; a
+/// value outside the byte range (like day 21's hull damage) is written
+/// out as a decimal number instead.
+///
+#[derive(Default)]
+struct TextBuffer {
+    text: String,
+    bytes: Vec<u8>
+}
+
+impl TextBuffer {
+    fn push(&mut self, value: i64) {
+        if (0..=255).contains(&value) {
+            self.bytes.push(value as u8);
+        } else {
+            self.flush_bytes();
+            self.text.push_str(&value.to_string());
+        }
+    }
+
+    fn flush_bytes(&mut self) {
+        self.text.push_str(&String::from_utf8_lossy(&self.bytes));
+        self.bytes.clear();
+    }
+
+    fn take(&mut self) -> String {
+        self.flush_bytes();
+        std::mem::take(&mut self.text)
+    }
+}
+
+impl IntCode<InputQueue> {
+    ///
+    /// Drives an ASCII program as a REPL. Everything the program prints up
+    /// to the point it blocks on input is handed to `handler` as one
+    /// string; the handler's reply is fed back followed by a newline, or
+    /// the dialog ends if it returns `None`. When the program terminates,
+    /// whatever it printed last is passed to the handler too, and the
+    /// reply is ignored.
+    ///
+    pub fn run_ascii_dialog<F>(program: &[i64], mut handler: F) -> Result<()> where
+        F: FnMut(&str) -> Option<String>
+    {
+        let mut machine = IntCode::init(program, InputQueue::new());
+        let mut buffer = TextBuffer::default();
+
+        loop {
+            match machine.run_until_output_or_input()? {
+                HaltReason::Output => {
+                    for value in machine.take_outputs() {
+                        buffer.push(value);
+                    }
+                },
+                HaltReason::NeedInput => {
+                    let reply = match handler(&buffer.take()) {
+                        Some(reply) => reply,
+                        None => return Ok(())
+                    };
+                    let input = machine.input_mut();
+                    for byte in reply.bytes().chain(Some(b'\n')) {
+                        input.push(byte as i64);
+                    }
+                },
+                HaltReason::Terminated => {
+                    let text = buffer.take();
+                    if !text.is_empty() {
+                        handler(&text);
+                    }
+                    return Ok(());
+                },
+                HaltReason::Running => unreachable!()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // Prints "> ", then echoes its input back one character at a time,
+    // prompting again after every newline. Never halts.
+    //
+    //  0: OUT #62; OUT #32
+    //  4: IN -> [100]
+    //  6: EQ [100], #10 -> [101]
+    // 10: OUT [100]
+    // 12: JT [101], #0
+    // 15: JT #1, #4
+    const ECHO: [i64; 18] = [104,62,104,32,3,100,1008,100,10,101,4,100,1005,101,0,1105,1,4];
+
+    #[test]
+    fn test_echo_dialog() {
+        let mut prompts = Vec::new();
+        let mut replies = vec!["hello", "wörld"].into_iter();
+
+        IntCode::run_ascii_dialog(&ECHO, |text| {
+            prompts.push(text.to_string());
+            replies.next().map(|reply| reply.to_string())
+        }).unwrap();
+
+        assert_eq!(prompts, ["> ", "hello\n> ", "wörld\n> "]);
+    }
+
+    #[test]
+    fn test_dialog_termination() {
+        let mut prompts = Vec::new();
+
+        // OUT #72; OUT #105; OUT #10; OUT #1000; HALT
+        IntCode::run_ascii_dialog(&[104,72,104,105,104,10,104,1000,99], |text| {
+            prompts.push(text.to_string());
+            Some("ignored".to_string())
+        }).unwrap();
+
+        assert_eq!(prompts, ["Hi\n1000"]);
+    }
+}
@@ -0,0 +1,105 @@
+//!
+//! Runs an IntCode program from a file against the terminal:
+//!
+//!     intcode-run [--ascii] PROGRAM
+//!
+//! By default outputs are printed one decimal number per line and inputs
+//! are read as whitespace separated integers. With `--ascii` the program
+//! talks text instead, as days 17, 21 and 25 do: output bytes are printed
+//! as they are, and each line typed is fed in followed by a newline.
+//! Lines are only read once the program blocks waiting for input.
+//!
+
+use std::env;
+use std::fs;
+use std::io::{self, BufRead, Write};
+
+use intcode::{HaltReason, InputQueue, IntCode, OutputFormat, Result};
+
+fn parse_program(text: &str) -> Result<Vec<i64>> {
+    text.split(',')
+        .map(|s| s.trim())
+        .filter(|s| !s.is_empty())
+        .map(|s| s.parse().map_err(|e| format!("invalid value \"{}\" in program: {}", s, e).into()))
+        .collect()
+}
+
+fn parse_args() -> Result<(String, OutputFormat)> {
+    let mut path = None;
+    let mut format = OutputFormat::Decimal;
+    for arg in env::args().skip(1) {
+        if arg == "--ascii" {
+            format = OutputFormat::Ascii;
+        } else if path.is_none() && !arg.starts_with("--") {
+            path = Some(arg);
+        } else {
+            return Err(format!("Unknown argument: {}", arg).into());
+        }
+    }
+
+    let path = path.ok_or("usage: intcode-run [--ascii] PROGRAM")?;
+    Ok((path, format))
+}
+
+//
+// Queues the inputs on one line of stdin, returning how many there were.
+//
+fn push_line(input: &mut InputQueue, line: &str, format: OutputFormat) -> Result<usize> {
+    match format {
+        OutputFormat::Ascii => {
+            for byte in line.bytes().chain(Some(b'\n')) {
+                input.push(byte as i64);
+            }
+            Ok(line.len() + 1)
+        },
+        OutputFormat::Decimal => {
+            let mut count = 0;
+            for token in line.split_whitespace() {
+                let value = token.parse().map_err(|e| format!("invalid input \"{}\": {}", token, e))?;
+                input.push(value);
+                count += 1;
+            }
+            Ok(count)
+        }
+    }
+}
+
+fn main() -> Result<()> {
+    let (path, format) = parse_args()?;
+    let program = parse_program(&fs::read_to_string(&path)?)?;
+    let mut machine = IntCode::init(&program, InputQueue::new());
+
+    let stdin = io::stdin();
+    let mut lines = stdin.lock().lines();
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+
+    loop {
+        match machine.run_until_output_or_input()? {
+            HaltReason::Output => {
+                for value in machine.take_outputs() {
+                    match format {
+                        OutputFormat::Ascii if (0..=255).contains(&value) => out.write_all(&[value as u8])?,
+                        OutputFormat::Ascii => write!(out, "{}", value)?,
+                        OutputFormat::Decimal => writeln!(out, "{}", value)?
+                    }
+                }
+            },
+            HaltReason::NeedInput => {
+                out.flush()?;
+                // blank lines carry no integers, so keep reading until one does
+                loop {
+                    let line = lines.next().ok_or("program is waiting for input, but stdin is closed")??;
+                    if push_line(machine.input_mut(), &line, format)? > 0 {
+                        break;
+                    }
+                }
+            },
+            HaltReason::Terminated => break,
+            HaltReason::Running => unreachable!()
+        }
+    }
+
+    out.flush()?;
+    Ok(())
+}
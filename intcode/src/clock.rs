@@ -0,0 +1,33 @@
+use std::time::{Duration, Instant};
+
+///
+/// Source of time for `run_for`; tests substitute a fake that advances
+/// by a fixed amount on every reading.
+///
+pub trait Clock {
+    /// Time elapsed since some fixed starting point.
+    fn now(&mut self) -> Duration;
+}
+
+#[derive(Debug)]
+pub struct SystemClock {
+    start: Instant
+}
+
+impl SystemClock {
+    pub fn new() -> SystemClock {
+        SystemClock { start: Instant::now() }
+    }
+}
+
+impl Default for SystemClock {
+    fn default() -> SystemClock {
+        SystemClock::new()
+    }
+}
+
+impl Clock for SystemClock {
+    fn now(&mut self) -> Duration {
+        self.start.elapsed()
+    }
+}
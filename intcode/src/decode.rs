@@ -0,0 +1,155 @@
+use crate::{Instruction, IntCodeError, ParameterType};
+
+type Result<T> = ::core::result::Result<T, IntCodeError>;
+
+///
+/// Decodes the instruction starting at `address`, returning it along with
+/// the address of the instruction that follows it.
+///
+pub fn decode(memory: &[i64], address: usize) -> Result<(Instruction, usize)> {
+    let mut decoder = Decoder { memory, address_ptr: address };
+    let instruction = decoder.read_instruction()?;
+    Ok((instruction, decoder.address_ptr))
+}
+
+//
+// Parameter modes still to be handed out, least significant digit first.
+// Parameters past the last digit are references.
+//
+struct Modes(i64);
+
+impl Modes {
+    fn next(&mut self) -> ParameterType {
+        let mode = match self.0 % 10 {
+            1 => ParameterType::Value(0),
+            2 => ParameterType::Relative(0),
+            _ => ParameterType::Ref(0)
+        };
+        self.0 /= 10;
+        mode
+    }
+}
+
+fn parse_op_code(input: i64) -> Result<(u32, Modes)> {
+    let op_code = input % 100;
+    let mut parameter_stream = input / 100;
+
+    while parameter_stream > 0 {
+        if parameter_stream % 10 > 2 {
+            return Err(IntCodeError::InvalidParameterMode(input));
+        }
+        parameter_stream /= 10;
+    }
+
+    Ok((op_code as u32, Modes(input / 100)))
+}
+
+struct Decoder<'a> {
+    memory: &'a [i64],
+    address_ptr: usize
+}
+
+impl<'a> Decoder<'a> {
+    fn read_parameter(
+        &mut self,
+        parameter_mode: &mut Modes,
+        is_writing: bool // If parameter is for a write operation, parameter type must be a reference
+    ) -> Result<ParameterType> {
+        let parameter_value = self.memory.get(self.address_ptr).ok_or(IntCodeError::AddressOutOfBounds(self.address_ptr))?;
+        let parameter_type = parameter_mode.next();
+
+        self.address_ptr += 1;
+
+        match parameter_type {
+            ParameterType::Ref(_) => {
+                if *parameter_value < 0 {
+                    return Err(IntCodeError::NegativeAddress(*parameter_value));
+                }
+                Ok(ParameterType::Ref(*parameter_value as usize))
+            },
+            ParameterType::Value(_) => {
+                if is_writing {
+                    Err(IntCodeError::WriteToImmediate)
+                } else {
+                    Ok(ParameterType::Value(*parameter_value))
+                }
+            },
+            ParameterType::Relative(_) => {
+                Ok(ParameterType::Relative(*parameter_value))
+            }
+        }
+    }
+
+    fn read_instruction(&mut self) -> Result<Instruction> {
+        let raw_op_code = *self.memory.get(self.address_ptr).ok_or(IntCodeError::AddressOutOfBounds(self.address_ptr))?;
+        self.address_ptr += 1;
+
+        let (op_code, mut parameter_mode) = parse_op_code(raw_op_code)?;
+
+        let instruction = match op_code {
+            1 => {
+                Instruction::Add {
+                    left_op: self.read_parameter(&mut parameter_mode, false)?,
+                    right_op: self.read_parameter(&mut parameter_mode, false)?,
+                    into: self.read_parameter(&mut parameter_mode, true)?
+                }
+            }
+            2 => {
+                Instruction::Mul {
+                    left_op: self.read_parameter(&mut parameter_mode, false)?,
+                    right_op: self.read_parameter(&mut parameter_mode, false)?,
+                    into: self.read_parameter(&mut parameter_mode, true)?
+                }
+            }
+            3 => {
+                Instruction::Input {
+                    into: self.read_parameter(&mut parameter_mode, true)?
+                }
+            },
+            4 => {
+                Instruction::Output {
+                    param: self.read_parameter(&mut parameter_mode, false)?
+                }
+            }
+            5 => {
+                Instruction::JumpIfTrue {
+                    cond: self.read_parameter(&mut parameter_mode, false)?,
+                    to: self.read_parameter(&mut parameter_mode, false)?
+                }
+            }
+            6 => {
+                Instruction::JumpIfFalse {
+                    cond: self.read_parameter(&mut parameter_mode, false)?,
+                    to: self.read_parameter(&mut parameter_mode, false)?
+                }
+            }
+            7 => {
+                Instruction::LessThan {
+                    left_op: self.read_parameter(&mut parameter_mode, false)?,
+                    right_op: self.read_parameter(&mut parameter_mode, false)?,
+                    into: self.read_parameter(&mut parameter_mode, true)?
+                }
+            },
+            8 => {
+                Instruction::Equals {
+                    left_op: self.read_parameter(&mut parameter_mode, false)?,
+                    right_op: self.read_parameter(&mut parameter_mode, false)?,
+                    into: self.read_parameter(&mut parameter_mode, true)?
+                }
+            }
+            9 => {
+                Instruction::RelativeBase {
+                    adjust: self.read_parameter(&mut parameter_mode, false)?
+                }
+            }
+            99 => {
+                Instruction::Terminate
+            }
+            _ => {
+                return Err(IntCodeError::InvalidOpcode(raw_op_code));
+            }
+        };
+
+        Ok(instruction)
+    }
+}
@@ -0,0 +1,110 @@
+use core::fmt;
+#[cfg(feature = "std")]
+use std::error::Error;
+
+#[cfg(feature = "std")]
+use crate::Instruction;
+
+///
+/// Wraps a failure raised while executing `instruction`; the original
+/// error is available through `source()`.
+///
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub struct InstructionError {
+    pub instruction: Instruction,
+    pub error: Box<dyn Error>
+}
+
+#[cfg(feature = "std")]
+impl fmt::Display for InstructionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.instruction, self.error)
+    }
+}
+
+#[cfg(feature = "std")]
+impl Error for InstructionError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(self.error.as_ref())
+    }
+}
+
+#[cfg(feature = "std")]
+#[derive(Debug, PartialEq)]
+pub struct UninitializedRead {
+    pub address: usize,
+    pub ip: usize
+}
+
+#[cfg(feature = "std")]
+impl fmt::Display for UninitializedRead {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "read of uninitialized address {} at ip {}", self.address, self.ip)
+    }
+}
+
+#[cfg(feature = "std")]
+impl Error for UninitializedRead {}
+
+#[cfg(feature = "std")]
+#[derive(Debug, PartialEq)]
+pub struct CycleDetected {
+    pub period: usize
+}
+
+#[cfg(feature = "std")]
+impl fmt::Display for CycleDetected {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "machine state repeats every {} ticks", self.period)
+    }
+}
+
+#[cfg(feature = "std")]
+impl Error for CycleDetected {}
+
+///
+/// Ways a program can be malformed or misbehave at runtime, so callers can
+/// tell e.g. a machine waiting for input apart from one that hit garbage.
+/// Errors raised while executing an instruction arrive wrapped in an
+/// `InstructionError`; `IntCodeError::find` looks through that.
+///
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum IntCodeError {
+    InvalidOpcode(i64),
+    InvalidParameterMode(i64),
+    WriteToImmediate,
+    OutOfInput,
+    NegativeAddress(i64),
+    AddressOutOfBounds(usize)
+}
+
+impl IntCodeError {
+    #[cfg(feature = "std")]
+    pub fn find<'a>(error: &'a (dyn Error + 'static)) -> Option<&'a IntCodeError> {
+        let mut error = Some(error);
+        while let Some(e) = error {
+            if let Some(found) = e.downcast_ref::<IntCodeError>() {
+                return Some(found);
+            }
+            error = e.source();
+        }
+        None
+    }
+}
+
+impl fmt::Display for IntCodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            IntCodeError::InvalidOpcode(op_code) => write!(f, "Invalid opcode: {}", op_code),
+            IntCodeError::InvalidParameterMode(op_code) => write!(f, "Invalid parameter mode in opcode: {}", op_code),
+            IntCodeError::WriteToImmediate => write!(f, "Invalid parameter type: parameter is for a write operation"),
+            IntCodeError::OutOfInput => write!(f, "Ran out of input"),
+            IntCodeError::NegativeAddress(address) => write!(f, "Negative address: {}", address),
+            IntCodeError::AddressOutOfBounds(address) => write!(f, "Address out of bounds: {}", address)
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl Error for IntCodeError {}
@@ -0,0 +1,124 @@
+//!
+//! Instruction execution, shared by the growable `IntCode` machine and the
+//! fixed-buffer `FixedMachine`. Only depends on `core`, so it is available
+//! without the `std` feature.
+//!
+
+use crate::{Instruction, IntCodeError, ParameterType};
+
+///
+/// Storage an instruction reads from and writes to. Implementations decide
+/// what happens outside the program: the `std` machine grows on writes and
+/// reads zeros, a fixed buffer fails with `AddressOutOfBounds`.
+///
+pub trait Memory {
+    type Error: From<IntCodeError>;
+
+    fn read(&self, address: usize) -> Result<i64, Self::Error>;
+    fn write(&mut self, address: usize, value: i64) -> Result<(), Self::Error>;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Registers {
+    /// Address of the next instruction to decode.
+    pub ip: usize,
+    pub relative_base: i64
+}
+
+///
+/// What executing an instruction did, besides updating memory and
+/// registers.
+///
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Effect {
+    Continue,
+    Input,
+    Output(i64),
+    Halt
+}
+
+impl Registers {
+    fn address(&self, parameter: ParameterType) -> Result<usize, IntCodeError> {
+        match parameter {
+            ParameterType::Ref(address) => Ok(address),
+            ParameterType::Relative(offset) => {
+                let address = self.relative_base + offset;
+                if address < 0 {
+                    return Err(IntCodeError::NegativeAddress(address));
+                }
+                Ok(address as usize)
+            },
+            ParameterType::Value(_) => Err(IntCodeError::WriteToImmediate)
+        }
+    }
+}
+
+pub fn resolve<M: Memory>(memory: &M, registers: &Registers, parameter: ParameterType) -> Result<i64, M::Error> {
+    match parameter {
+        ParameterType::Value(value) => Ok(value),
+        _ => memory.read(registers.address(parameter)?)
+    }
+}
+
+fn write<M: Memory>(memory: &mut M, registers: &Registers, into: ParameterType, value: i64) -> Result<(), M::Error> {
+    memory.write(registers.address(into)?, value)
+}
+
+///
+/// Executes a decoded instruction. `registers.ip` must already point past
+/// it; jumps overwrite it. `input` is only called by input instructions,
+/// and `None` fails with `OutOfInput` before anything is written.
+///
+pub fn execute<M, I>(memory: &mut M, registers: &mut Registers, instruction: Instruction, input: I) -> Result<Effect, M::Error> where
+    M: Memory,
+    I: FnOnce() -> Option<i64>
+{
+    match instruction {
+        Instruction::Add { left_op, right_op, into } => {
+            let sum = resolve(memory, registers, left_op)? + resolve(memory, registers, right_op)?;
+            write(memory, registers, into, sum)?;
+        }
+        Instruction::Mul { left_op, right_op, into } => {
+            let product = resolve(memory, registers, left_op)? * resolve(memory, registers, right_op)?;
+            write(memory, registers, into, product)?;
+        }
+        Instruction::Input { into } => {
+            let input_value = input().ok_or(IntCodeError::OutOfInput)?;
+            write(memory, registers, into, input_value)?;
+            return Ok(Effect::Input);
+        }
+        Instruction::Output { param } => {
+            return Ok(Effect::Output(resolve(memory, registers, param)?));
+        }
+        Instruction::JumpIfTrue { cond, to } => {
+            if resolve(memory, registers, cond)? != 0 {
+                registers.ip = resolve(memory, registers, to)? as usize;
+            }
+        }
+        Instruction::JumpIfFalse { cond, to } => {
+            if resolve(memory, registers, cond)? == 0 {
+                registers.ip = resolve(memory, registers, to)? as usize;
+            }
+        }
+        Instruction::LessThan { left_op, right_op, into } => {
+            let less_than = if resolve(memory, registers, left_op)? < resolve(memory, registers, right_op)? {
+                1
+            } else { 0 };
+            write(memory, registers, into, less_than)?;
+        }
+        Instruction::Equals { left_op, right_op, into } => {
+            let equals = if resolve(memory, registers, left_op)? == resolve(memory, registers, right_op)? {
+                1
+            } else { 0 };
+            write(memory, registers, into, equals)?;
+        }
+        Instruction::RelativeBase { adjust } => {
+            registers.relative_base += resolve(memory, registers, adjust)?;
+        }
+        Instruction::Terminate => {
+            return Ok(Effect::Halt);
+        }
+    };
+
+    Ok(Effect::Continue)
+}
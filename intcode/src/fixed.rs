@@ -0,0 +1,151 @@
+//!
+//! A machine running in a caller-provided buffer, for contexts without an
+//! allocator. The buffer is all the memory there is: the program is
+//! loaded at the start of it, and touching anything past its end fails
+//! with `AddressOutOfBounds` instead of growing it.
+//!
+
+use crate::decode;
+use crate::exec::{self, Effect, Memory, Registers};
+use crate::IntCodeError;
+
+struct FixedMemory<'a>(&'a mut [i64]);
+
+impl<'a> Memory for FixedMemory<'a> {
+    type Error = IntCodeError;
+
+    fn read(&self, address: usize) -> Result<i64, IntCodeError> {
+        self.0.get(address).copied().ok_or(IntCodeError::AddressOutOfBounds(address))
+    }
+
+    fn write(&mut self, address: usize, value: i64) -> Result<(), IntCodeError> {
+        let cell = self.0.get_mut(address).ok_or(IntCodeError::AddressOutOfBounds(address))?;
+        *cell = value;
+        Ok(())
+    }
+}
+
+pub struct FixedMachine<'a> {
+    memory: FixedMemory<'a>,
+    registers: Registers,
+    is_terminated: bool
+}
+
+impl<'a> FixedMachine<'a> {
+    ///
+    /// Runs the program already in `memory`, which should be followed by
+    /// as much zeroed space as the program needs.
+    ///
+    pub fn new(memory: &'a mut [i64]) -> FixedMachine<'a> {
+        FixedMachine {
+            memory: FixedMemory(memory),
+            registers: Registers::default(),
+            is_terminated: false
+        }
+    }
+
+    pub fn memory(&self) -> &[i64] {
+        self.memory.0
+    }
+
+    pub fn registers(&self) -> Registers {
+        self.registers
+    }
+
+    pub fn is_terminated(&self) -> bool {
+        self.is_terminated
+    }
+
+    ///
+    /// Executes a single instruction. If it fails (including running out
+    /// of input) the instruction pointer is left on it, so it can be
+    /// retried.
+    ///
+    pub fn step<I>(&mut self, input: I) -> Result<Effect, IntCodeError> where
+        I: FnOnce() -> Option<i64>
+    {
+        if self.is_terminated {
+            return Ok(Effect::Halt);
+        }
+
+        let ip = self.registers.ip;
+        let (instruction, next) = decode(self.memory.0, ip)?;
+        self.registers.ip = next;
+
+        let effect = exec::execute(&mut self.memory, &mut self.registers, instruction, input);
+        match effect {
+            Ok(Effect::Halt) => self.is_terminated = true,
+            Err(_) => self.registers.ip = ip,
+            _ => {}
+        }
+        effect
+    }
+
+    ///
+    /// Runs to termination, pulling input from `input` and handing every
+    /// output to `output`.
+    ///
+    pub fn run<I, O>(&mut self, mut input: I, mut output: O) -> Result<(), IntCodeError> where
+        I: FnMut() -> Option<i64>,
+        O: FnMut(i64)
+    {
+        loop {
+            match self.step(&mut input)? {
+                Effect::Output(value) => output(value),
+                Effect::Halt => return Ok(()),
+                Effect::Continue | Effect::Input => {}
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_fixed_buffer() {
+        // outputs 999, 1000 or 1001 for input below, equal to or above 8
+        let program = [3,21,1008,21,8,20,1005,20,22,107,8,21,20,1006,20,31,1106,0,36,98,0,0,1002,21,125,20,4,20,1105,1,46,104,999,1105,1,46,1101,1000,1,20,4,20,1105,1,46,98,99];
+
+        for (input, expected) in [(7, 999), (8, 1000), (9, 1001)] {
+            let mut buffer = [0; 64];
+            buffer[..program.len()].copy_from_slice(&program);
+
+            let mut machine = FixedMachine::new(&mut buffer);
+            let mut outputs = Vec::new();
+            machine.run(|| Some(input), |value| outputs.push(value)).unwrap();
+            assert_eq!(outputs, [expected]);
+            assert!(machine.is_terminated());
+        }
+    }
+
+    #[test]
+    fn test_fixed_buffer_out_of_bounds() {
+        // 0: ADD #1, #1 -> [5]; 4: HALT
+        let mut buffer = [1101,1,1,5,99];
+        let mut machine = FixedMachine::new(&mut buffer);
+        assert_eq!(machine.run(|| None, |_| {}), Err(IntCodeError::AddressOutOfBounds(5)));
+        assert_eq!(machine.registers().ip, 0);
+        assert_eq!(machine.memory(), [1101,1,1,5,99]);
+
+        // relative reads past the end fail too, rather than reading zero
+        let mut buffer = [109,10,22201,0,0,0,99];
+        let mut machine = FixedMachine::new(&mut buffer);
+        assert_eq!(machine.run(|| None, |_| {}), Err(IntCodeError::AddressOutOfBounds(10)));
+    }
+
+    #[test]
+    fn test_fixed_buffer_waits_for_input() {
+        // 0: IN -> [5]; 2: OUT [5]; 4: HALT
+        let mut buffer = [3,5,4,5,99,0];
+        let mut machine = FixedMachine::new(&mut buffer);
+
+        assert_eq!(machine.step(|| None), Err(IntCodeError::OutOfInput));
+        assert_eq!(machine.registers().ip, 0);
+        assert_eq!(machine.step(|| Some(42)), Ok(Effect::Input));
+        assert_eq!(machine.step(|| None), Ok(Effect::Output(42)));
+        assert_eq!(machine.step(|| None), Ok(Effect::Halt));
+        assert!(machine.is_terminated());
+    }
+}
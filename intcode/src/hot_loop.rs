@@ -0,0 +1,84 @@
+use std::collections::BTreeSet;
+use std::collections::VecDeque;
+use std::fmt;
+
+// Number of recent instruction pointers considered part of the current loop.
+const WINDOW_SIZE: usize = 64;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct HotLoop {
+    pub addresses: Vec<usize>,
+    pub ticks: usize
+}
+
+impl fmt::Display for HotLoop {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let first = self.addresses.first().unwrap_or(&0);
+        let last = self.addresses.last().unwrap_or(&0);
+        write!(f, "spinning in addresses {}\u{2013}{} for {} ticks without I/O", first, last, self.ticks)
+    }
+}
+
+pub(crate) struct HotLoopDetector {
+    threshold: usize,
+    callback: Box<dyn FnMut(&HotLoop) + Send>,
+    window: VecDeque<usize>,
+    loop_addresses: BTreeSet<usize>,
+    visited: BTreeSet<usize>,
+    ticks: usize,
+    reported: bool
+}
+
+impl HotLoopDetector {
+    pub(crate) fn new(threshold: usize, callback: Box<dyn FnMut(&HotLoop) + Send>) -> HotLoopDetector {
+        HotLoopDetector {
+            threshold,
+            callback,
+            window: VecDeque::with_capacity(WINDOW_SIZE),
+            loop_addresses: BTreeSet::new(),
+            visited: BTreeSet::new(),
+            ticks: 0,
+            reported: false
+        }
+    }
+
+    pub(crate) fn record(&mut self, address: usize) {
+        if self.window.len() == WINDOW_SIZE {
+            self.window.pop_front();
+        }
+        self.window.push_back(address);
+
+        if self.loop_addresses.contains(&address) {
+            self.ticks += 1;
+            self.visited.insert(address);
+        } else {
+            //
+            // Reached an address outside the current set, so whatever we
+            // were looping in has been left. Start over with the addresses
+            // executed recently.
+            //
+            self.loop_addresses = self.window.iter().cloned().collect();
+            self.visited.clear();
+            self.ticks = 0;
+            self.reported = false;
+        }
+
+        if !self.reported && self.ticks > self.threshold {
+            self.reported = true;
+            let hot_loop = HotLoop {
+                addresses: self.visited.iter().cloned().collect(),
+                ticks: self.ticks
+            };
+            (self.callback)(&hot_loop);
+        }
+    }
+
+    // I/O means the machine is making progress.
+    pub(crate) fn reset(&mut self) {
+        self.window.clear();
+        self.loop_addresses.clear();
+        self.visited.clear();
+        self.ticks = 0;
+        self.reported = false;
+    }
+}
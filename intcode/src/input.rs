@@ -0,0 +1,35 @@
+use std::collections::VecDeque;
+
+///
+/// Input stream that can be fed while the machine is paused, for callers
+/// that only learn the next input after seeing some output.
+///
+#[derive(Debug, Default)]
+pub struct InputQueue {
+    queue: VecDeque<i64>
+}
+
+impl InputQueue {
+    pub fn new() -> InputQueue {
+        InputQueue::default()
+    }
+
+    pub fn push(&mut self, value: i64) {
+        self.queue.push_back(value);
+    }
+
+    pub fn len(&self) -> usize {
+        self.queue.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+}
+
+impl Iterator for InputQueue {
+    type Item = i64;
+    fn next(&mut self) -> Option<i64> {
+        self.queue.pop_front()
+    }
+}
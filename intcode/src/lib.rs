@@ -0,0 +1,1168 @@
+//!
+//! Shared IntCode interpreter, extracted from the day 9 solution so that
+//! tooling (diagnostics, tracing, ...) only has to be written once.
+//!
+//! Decoding and executing instructions only needs `core`. Everything else,
+//! including the `IntCode` machine whose memory grows as the program
+//! writes to it, is behind the default `std` feature; without it the crate
+//! is `no_std` and programs run in a fixed buffer through `FixedMachine`.
+//!
+
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+use core::fmt;
+
+#[cfg(feature = "std")]
+use std::collections::hash_map::DefaultHasher;
+#[cfg(feature = "std")]
+use std::collections::HashMap;
+#[cfg(feature = "std")]
+use std::collections::HashSet;
+#[cfg(feature = "std")]
+use std::collections::VecDeque;
+#[cfg(feature = "std")]
+use std::hash::{Hash, Hasher};
+#[cfg(feature = "std")]
+use std::io::Write;
+#[cfg(feature = "std")]
+use std::sync::Arc;
+#[cfg(feature = "std")]
+use std::time::Duration;
+
+#[cfg(feature = "std")]
+use log::trace;
+
+#[cfg(feature = "std")]
+mod ascii;
+#[cfg(feature = "std")]
+mod clock;
+mod decode;
+mod error;
+pub mod exec;
+mod fixed;
+#[cfg(feature = "std")]
+mod hot_loop;
+#[cfg(feature = "std")]
+mod input;
+#[cfg(feature = "wasm-bindgen")]
+pub mod wasm;
+
+#[cfg(feature = "std")]
+pub use clock::{Clock, SystemClock};
+pub use decode::decode;
+pub use error::IntCodeError;
+#[cfg(feature = "std")]
+pub use error::{CycleDetected, InstructionError, UninitializedRead};
+pub use fixed::FixedMachine;
+#[cfg(feature = "std")]
+pub use hot_loop::HotLoop;
+#[cfg(feature = "std")]
+pub use input::InputQueue;
+#[cfg(feature = "std")]
+use exec::{Effect, Memory, Registers};
+#[cfg(feature = "std")]
+use hot_loop::HotLoopDetector;
+
+#[cfg(feature = "std")]
+pub type Result<T> = ::std::result::Result<T, Box<dyn ::std::error::Error>>;
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum ParameterType {
+    Ref(usize),
+    Value(i64),
+    Relative(i64)
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Instruction {
+    Add { left_op: ParameterType, right_op: ParameterType, into: ParameterType },
+    Mul { left_op: ParameterType, right_op: ParameterType, into: ParameterType },
+    Input { into: ParameterType },
+    Output { param: ParameterType },
+    JumpIfTrue { cond: ParameterType, to: ParameterType },
+    JumpIfFalse { cond: ParameterType, to: ParameterType },
+    LessThan { left_op: ParameterType, right_op: ParameterType, into: ParameterType },
+    Equals { left_op: ParameterType, right_op: ParameterType, into: ParameterType },
+    RelativeBase { adjust: ParameterType },
+    Terminate,
+}
+
+impl fmt::Display for ParameterType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParameterType::Ref(address) => write!(f, "[{}]", address),
+            ParameterType::Value(value) => write!(f, "#{}", value),
+            ParameterType::Relative(offset) if *offset < 0 => write!(f, "r-{}", -offset),
+            ParameterType::Relative(offset) => write!(f, "r+{}", offset)
+        }
+    }
+}
+
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Instruction::Add { left_op, right_op, into } => write!(f, "ADD {}, {} -> {}", left_op, right_op, into),
+            Instruction::Mul { left_op, right_op, into } => write!(f, "MUL {}, {} -> {}", left_op, right_op, into),
+            Instruction::Input { into } => write!(f, "IN -> {}", into),
+            Instruction::Output { param } => write!(f, "OUT {}", param),
+            Instruction::JumpIfTrue { cond, to } => write!(f, "JT {}, {}", cond, to),
+            Instruction::JumpIfFalse { cond, to } => write!(f, "JF {}, {}", cond, to),
+            Instruction::LessThan { left_op, right_op, into } => write!(f, "LT {}, {} -> {}", left_op, right_op, into),
+            Instruction::Equals { left_op, right_op, into } => write!(f, "EQ {}, {} -> {}", left_op, right_op, into),
+            Instruction::RelativeBase { adjust } => write!(f, "RB {}", adjust),
+            Instruction::Terminate => write!(f, "HALT")
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum HaltReason {
+    Running,
+    Output,
+    NeedInput,
+    Terminated
+}
+
+///
+/// Result of `run_for`: why the machine stopped, or `None` if the time
+/// slice ran out first, and how many instructions were executed.
+///
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RunStatus {
+    pub reason: Option<HaltReason>,
+    pub ticks: usize
+}
+
+///
+/// How `run_to_writer_as` writes each output: as a decimal number on its
+/// own line, or for ASCII programs as the byte itself. In ASCII a value
+/// outside the byte range (like day 21's hull damage) is written as a
+/// decimal number instead, as `run_ascii_dialog` does.
+///
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OutputFormat {
+    Decimal,
+    Ascii
+}
+
+#[cfg(feature = "std")]
+pub struct IntCode<T: Iterator> {
+    // the program as loaded, for `reset`. Memory starts out sharing it and
+    // is only copied on the first write, so a machine that is never reset
+    // holds a single copy. Arc rather than Rc so the machine stays Send.
+    program: Arc<Vec<i64>>,
+    memory: Arc<Vec<i64>>,
+    address_ptr: usize,
+    input_stream: T,
+    output_buffer: VecDeque<i64>,
+    is_terminated: bool,
+    relative_ptr: i64,
+    instruction_ptr: usize,
+    initialized_len: usize,
+    strict_reads: Option<HashSet<usize>>,
+    tracing: bool,
+    trace: Vec<String>,
+    last_write: Option<(usize, i64)>,
+    hot_loop: Option<HotLoopDetector>,
+    output_observer: Option<Box<dyn FnMut(i64) + Send>>
+}
+
+#[cfg(feature = "std")]
+pub struct OutputStream<T: Iterator>(IntCode<T>);
+
+#[cfg(feature = "std")]
+impl<T> Iterator for OutputStream<T> where
+    T: Iterator<Item = i64>
+{
+    type Item = i64;
+    fn next(&mut self) -> Option<i64> {
+        if !self.0.output_buffer.is_empty() {
+            self.0.output_buffer.pop_front()
+        } else {
+            self.0.run_to_next_output()
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T> IntCode<T> where
+    T: Iterator<Item = i64> {
+    pub fn init(memory: &[i64], input_stream: T) -> IntCode<T> {
+        IntCode::from_program(memory.to_vec(), input_stream)
+    }
+
+    fn from_program(program: Vec<i64>, input_stream: T) -> IntCode<T> {
+        let initialized_len = program.len();
+        let program = Arc::new(program);
+        IntCode {
+            memory: Arc::clone(&program),
+            program,
+            address_ptr: 0,
+            input_stream,
+            output_buffer: VecDeque::new(),
+            is_terminated: false,
+            relative_ptr: 0,
+            instruction_ptr: 0,
+            initialized_len,
+            strict_reads: None,
+            tracing: false,
+            trace: Vec::new(),
+            last_write: None,
+            hot_loop: None,
+            output_observer: None
+        }
+    }
+
+    ///
+    /// Like `init`, but overwrites some addresses of the program first,
+    /// e.g. day 2's noun and verb. Patching past the end of the program is
+    /// an `AddressOutOfBounds` error. `reset` goes back to the patched
+    /// program.
+    ///
+    pub fn init_with_patches(memory: &[i64], patches: &[(usize, i64)], input_stream: T) -> Result<IntCode<T>> {
+        let mut program = memory.to_vec();
+        for &(address, value) in patches {
+            let cell = program.get_mut(address).ok_or(IntCodeError::AddressOutOfBounds(address))?;
+            *cell = value;
+        }
+        Ok(IntCode::from_program(program, input_stream))
+    }
+
+    ///
+    /// Loads a new program into the machine and resets it to its initial
+    /// state, keeping the input stream and options such as tracing. The
+    /// memory buffer is reused, so running many variants of a program
+    /// doesn't allocate a fresh copy for each.
+    ///
+    pub fn reload(&mut self, program: &[i64]) {
+        if **self.program != *program {
+            self.program = Arc::new(program.to_vec());
+        }
+        self.reset();
+    }
+
+    ///
+    /// Puts the machine back to the state `init` left it in, with the
+    /// program it was given (or last reloaded), keeping the input stream
+    /// and options such as tracing. Pending outputs are dropped.
+    ///
+    pub fn reset(&mut self) {
+        // refill a buffer this machine owns rather than drop it, so a
+        // program that writes to memory doesn't allocate on every run
+        match Arc::get_mut(&mut self.memory) {
+            Some(memory) => {
+                memory.clear();
+                memory.extend_from_slice(&self.program);
+            }
+            None => self.memory = Arc::clone(&self.program)
+        }
+        self.address_ptr = 0;
+        self.output_buffer.clear();
+        self.is_terminated = false;
+        self.relative_ptr = 0;
+        self.instruction_ptr = 0;
+        self.initialized_len = self.program.len();
+        if let Some(written) = self.strict_reads.as_mut() {
+            written.clear();
+        }
+        self.trace.clear();
+        self.last_write = None;
+        self.reset_hot_loop();
+    }
+
+    ///
+    /// In strict mode, reading an address that is neither part of the
+    /// program nor has been written to fails with `UninitializedRead`
+    /// instead of yielding 0. Enable it before running the program.
+    ///
+    pub fn set_strict_reads(&mut self, strict: bool) {
+        self.strict_reads = if strict {
+            Some(HashSet::new())
+        } else {
+            None
+        };
+    }
+
+    ///
+    /// Records every executed instruction, the values of the operands it
+    /// read and the memory write it made, e.g.
+    /// `ADD [0], [0] -> [0] | 1, 1 | [0] <- 2`.
+    ///
+    pub fn enable_trace(&mut self, on: bool) {
+        self.tracing = on;
+    }
+
+    pub fn trace(&self) -> &[String] {
+        &self.trace
+    }
+
+    ///
+    /// Calls `callback` whenever the machine spends more than `threshold`
+    /// ticks cycling through the same small set of addresses without
+    /// consuming input or producing output.
+    ///
+    pub fn detect_hot_loops<F>(&mut self, threshold: usize, callback: F) where
+        F: FnMut(&HotLoop) + Send + 'static
+    {
+        self.hot_loop = Some(HotLoopDetector::new(threshold, Box::new(callback)));
+    }
+
+    ///
+    /// Calls `observer` with every value the program outputs, as it is
+    /// produced and before anyone reads it, e.g. to watch the raw stream
+    /// of a machine whose outputs are consumed elsewhere. Machines have no
+    /// observer unless one is installed.
+    ///
+    pub fn on_output<F>(&mut self, observer: F) where
+        F: FnMut(i64) + Send + 'static
+    {
+        self.output_observer = Some(Box::new(observer));
+    }
+
+    pub fn is_terminated(&self) -> bool {
+        self.is_terminated
+    }
+
+    pub fn memory(&self) -> &[i64] {
+        &self.memory
+    }
+
+    pub fn memory_mut(&mut self) -> &mut [i64] {
+        Arc::make_mut(&mut self.memory).as_mut_slice()
+    }
+
+    ///
+    /// Gives up the machine for its memory, e.g. to inspect what a program
+    /// left behind without copying it.
+    ///
+    pub fn into_memory(self) -> Vec<i64> {
+        drop(self.program);
+        Arc::try_unwrap(self.memory).unwrap_or_else(|memory| (*memory).clone())
+    }
+
+    ///
+    /// Replaces the input stream, e.g. to run the program again after a
+    /// `reset` with different input.
+    ///
+    pub fn set_input(&mut self, input_stream: T) {
+        self.input_stream = input_stream;
+    }
+
+    pub fn input_mut(&mut self) -> &mut T {
+        &mut self.input_stream
+    }
+
+    pub fn take_outputs(&mut self) -> Vec<i64> {
+        self.output_buffer.drain(..).collect()
+    }
+
+    pub fn output_stream(self) -> OutputStream<T> {
+        OutputStream(self)
+    }
+
+    pub fn run_to_next_output(&mut self) -> Option<i64> {
+        while self.output_buffer.is_empty() {
+            // bad code; output iterator should be a result
+            match self.poll().unwrap() {
+                HaltReason::Terminated => break,
+                HaltReason::NeedInput => panic!("Ran out of input"),
+                HaltReason::Running | HaltReason::Output => {}
+            }
+        }
+
+        self.output_buffer.pop_front()
+    }
+
+    fn read_instruction(&mut self) -> Result<Instruction> {
+        let (instruction, next_ptr) = decode(&self.memory, self.address_ptr)?;
+        self.address_ptr = next_ptr;
+        Ok(instruction)
+    }
+
+    pub fn run_tick(&mut self) -> Result<()> {
+        self.instruction_ptr = self.address_ptr;
+        let instruction = self.read_instruction()?;
+        trace!("{}: {}", self.instruction_ptr, instruction);
+
+        let operands = if self.tracing {
+            Some(self.trace_operands(&instruction))
+        } else {
+            None
+        };
+        self.last_write = None;
+
+        let mut registers = Registers { ip: self.address_ptr, relative_base: self.relative_ptr };
+        let input_stream = &mut self.input_stream;
+        let mut cells = Cells {
+            memory: &mut self.memory,
+            initialized_len: self.initialized_len,
+            strict_reads: &mut self.strict_reads,
+            ip: self.instruction_ptr,
+            last_write: &mut self.last_write
+        };
+        let effect = exec::execute(&mut cells, &mut registers, instruction, || input_stream.next())
+            .map_err(|error| InstructionError { instruction, error })?;
+
+        // only instructions that ran count, so an input instruction being
+        // retried by `poll` doesn't look like a loop
+        if let Some(detector) = self.hot_loop.as_mut() {
+            detector.record(self.instruction_ptr);
+        }
+
+        self.address_ptr = registers.ip;
+        self.relative_ptr = registers.relative_base;
+        match effect {
+            Effect::Input => self.reset_hot_loop(),
+            Effect::Output(value) => {
+                if let Some(observer) = self.output_observer.as_mut() {
+                    observer(value);
+                }
+                self.output_buffer.push_back(value);
+                self.reset_hot_loop();
+            },
+            Effect::Halt => self.is_terminated = true,
+            Effect::Continue => {}
+        }
+
+        if let Some(operands) = operands {
+            let mut entry = instruction.to_string();
+            if !operands.is_empty() {
+                entry += &format!(" | {}", operands.join(", "));
+            }
+            if let Some((address, value)) = self.last_write {
+                entry += &format!(" | [{}] <- {}", address, value);
+            }
+            self.trace.push(entry);
+        }
+
+        Ok(())
+    }
+
+    fn trace_operands(&mut self, instruction: &Instruction) -> Vec<String> {
+        let read = match *instruction {
+            Instruction::Add { left_op, right_op, .. } |
+            Instruction::Mul { left_op, right_op, .. } |
+            Instruction::LessThan { left_op, right_op, .. } |
+            Instruction::Equals { left_op, right_op, .. } => vec![left_op, right_op],
+            Instruction::JumpIfTrue { cond, to } |
+            Instruction::JumpIfFalse { cond, to } => vec![cond, to],
+            Instruction::Output { param } => vec![param],
+            Instruction::RelativeBase { adjust } => vec![adjust],
+            Instruction::Input { .. } | Instruction::Terminate => vec![]
+        };
+
+        let registers = Registers { ip: self.address_ptr, relative_base: self.relative_ptr };
+        let cells = Cells {
+            memory: &mut self.memory,
+            initialized_len: self.initialized_len,
+            strict_reads: &mut self.strict_reads,
+            ip: self.instruction_ptr,
+            last_write: &mut self.last_write
+        };
+
+        read.into_iter()
+            .map(|parameter| match exec::resolve(&cells, &registers, parameter) {
+                Ok(value) => value.to_string(),
+                Err(_) => "?".to_string()
+            })
+            .collect()
+    }
+
+    ///
+    /// Runs until the machine produces an output, terminates, or needs
+    /// input that is not available yet. In the last case the input
+    /// instruction is not consumed, so running again after feeding the
+    /// input stream (see `input_mut`) resumes from it.
+    ///
+    pub fn run_until_output_or_input(&mut self) -> Result<HaltReason> {
+        loop {
+            let reason = self.poll()?;
+            if reason != HaltReason::Running {
+                return Ok(reason);
+            }
+        }
+    }
+
+    ///
+    /// Like `run_until_output_or_input`, but also gives up once `duration`
+    /// has passed, so a caller can interleave the machine with other work
+    /// without threads. The machine is only ever paused between
+    /// instructions.
+    ///
+    pub fn run_for(&mut self, duration: Duration) -> Result<RunStatus> {
+        self.run_for_with_clock(duration, &mut SystemClock::new())
+    }
+
+    pub fn run_for_with_clock<C: Clock>(&mut self, duration: Duration, clock: &mut C) -> Result<RunStatus> {
+        let deadline = clock.now() + duration;
+        let mut ticks = 0;
+
+        while clock.now() < deadline {
+            let was_terminated = self.is_terminated;
+            let reason = self.poll()?;
+
+            if reason != HaltReason::NeedInput && !was_terminated {
+                ticks += 1;
+            }
+            if reason != HaltReason::Running {
+                return Ok(RunStatus { reason: Some(reason), ticks });
+            }
+        }
+
+        Ok(RunStatus { reason: None, ticks })
+    }
+
+    ///
+    /// Executes at most a single instruction and reports the state the
+    /// machine is left in: `Output` if that instruction produced output,
+    /// `NeedInput` if it is an input instruction with no input available
+    /// (it is left to be retried), `Terminated` once halted, and `Running`
+    /// otherwise. Lets callers interleave input and output at instruction
+    /// granularity.
+    ///
+    pub fn poll(&mut self) -> Result<HaltReason> {
+        if self.is_terminated {
+            return Ok(HaltReason::Terminated);
+        }
+
+        if let Err(error) = self.run_tick() {
+            let needs_input = IntCodeError::find(error.as_ref()) == Some(&IntCodeError::OutOfInput);
+            if !needs_input {
+                return Err(error);
+            }
+
+            self.address_ptr = self.instruction_ptr;
+            return Ok(HaltReason::NeedInput);
+        }
+
+        if self.is_terminated {
+            Ok(HaltReason::Terminated)
+        } else if !self.output_buffer.is_empty() {
+            Ok(HaltReason::Output)
+        } else {
+            Ok(HaltReason::Running)
+        }
+    }
+
+    fn reset_hot_loop(&mut self) {
+        if let Some(detector) = self.hot_loop.as_mut() {
+            detector.reset();
+        }
+    }
+
+    pub fn run_to_termination(&mut self) -> Result<()> {
+        while !self.is_terminated {
+            self.run_tick()?;
+        }
+        Ok(())
+    }
+
+    ///
+    /// Runs to termination, writing every output to `w` as it is produced
+    /// instead of collecting it, one decimal number per line.
+    ///
+    pub fn run_to_writer<W: Write>(&mut self, w: &mut W) -> Result<()> {
+        self.run_to_writer_as(w, OutputFormat::Decimal)
+    }
+
+    pub fn run_to_writer_as<W: Write>(&mut self, w: &mut W, format: OutputFormat) -> Result<()> {
+        loop {
+            for value in self.output_buffer.drain(..) {
+                match format {
+                    OutputFormat::Ascii if (0..=255).contains(&value) => w.write_all(&[value as u8])?,
+                    OutputFormat::Ascii => write!(w, "{}", value)?,
+                    OutputFormat::Decimal => writeln!(w, "{}", value)?
+                }
+            }
+
+            if self.is_terminated {
+                return Ok(());
+            }
+            self.run_tick()?;
+        }
+    }
+
+    ///
+    /// Hash of everything that determines how the machine continues,
+    /// apart from the input stream: memory, instruction pointer, relative
+    /// base and output that has not been consumed yet.
+    ///
+    pub fn state_fingerprint(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.memory.hash(&mut hasher);
+        self.address_ptr.hash(&mut hasher);
+        self.relative_ptr.hash(&mut hasher);
+        self.output_buffer.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    fn snapshot(&self) -> MachineState {
+        MachineState {
+            memory: self.memory.clone(),
+            address_ptr: self.address_ptr,
+            relative_ptr: self.relative_ptr,
+            output_buffer: self.output_buffer.clone()
+        }
+    }
+
+    ///
+    /// Runs to termination, sampling the machine state every `k` ticks.
+    /// A sampled state that was already seen means the program will never
+    /// terminate, and `CycleDetected` is returned instead. The reported
+    /// period is a multiple of `k`, so it may be a multiple of the true
+    /// period. Programs that read input are not guaranteed to cycle even
+    /// when their state repeats, since the input stream is not part of it.
+    ///
+    pub fn run_with_cycle_detection(&mut self, k: usize) -> Result<()> {
+        let k = k.max(1);
+        let mut seen: HashMap<u64, Vec<(usize, MachineState)>> = HashMap::new();
+        let mut ticks = 0;
+
+        while !self.is_terminated {
+            if ticks % k == 0 {
+                let fingerprint = self.state_fingerprint();
+                let state = self.snapshot();
+                let candidates = seen.entry(fingerprint).or_default();
+
+                // fingerprints may collide; only an identical state proves a cycle
+                if let Some((tick, _)) = candidates.iter().find(|(_, seen_state)| *seen_state == state) {
+                    return Err(CycleDetected { period: ticks - tick }.into());
+                }
+                candidates.push((ticks, state));
+            }
+
+            self.run_tick()?;
+            ticks += 1;
+        }
+        Ok(())
+    }
+}
+
+///
+/// Every address where two memory images differ, with the value in each,
+/// to pin down where a run went wrong. Past the end of the shorter image
+/// memory reads as zero, as it does for a running machine.
+///
+#[cfg(feature = "std")]
+pub fn memory_diff(a: &[i64], b: &[i64]) -> Vec<(usize, i64, i64)> {
+    (0..a.len().max(b.len()))
+        .map(|address| (address, *a.get(address).unwrap_or(&0), *b.get(address).unwrap_or(&0)))
+        .filter(|(_, a, b)| a != b)
+        .collect()
+}
+
+//
+// The machine's memory as seen by an executing instruction: it grows on
+// writes, reads past the end as zero unless strict reads are on, and
+// remembers the last write for the trace.
+//
+#[cfg(feature = "std")]
+struct Cells<'a> {
+    memory: &'a mut Arc<Vec<i64>>,
+    initialized_len: usize,
+    strict_reads: &'a mut Option<HashSet<usize>>,
+    ip: usize,
+    last_write: &'a mut Option<(usize, i64)>
+}
+
+#[cfg(feature = "std")]
+impl<'a> Memory for Cells<'a> {
+    type Error = Box<dyn ::std::error::Error>;
+
+    fn read(&self, address: usize) -> Result<i64> {
+        if let Some(written) = self.strict_reads.as_ref() {
+            if address >= self.initialized_len && !written.contains(&address) {
+                return Err(UninitializedRead { address, ip: self.ip }.into());
+            }
+        }
+
+        Ok(*self.memory.get(address).unwrap_or(&0))
+    }
+
+    fn write(&mut self, address: usize, value: i64) -> Result<()> {
+        let memory = Arc::make_mut(self.memory);
+        if address >= memory.len() {
+            memory.resize(address + 1, 0);
+        }
+        if let Some(written) = self.strict_reads.as_mut() {
+            if address >= self.initialized_len {
+                written.insert(address);
+            }
+        }
+
+        memory[address] = value;
+        *self.last_write = Some((address, value));
+        Ok(())
+    }
+}
+
+#[cfg(feature = "std")]
+#[derive(PartialEq)]
+struct MachineState {
+    memory: Arc<Vec<i64>>,
+    address_ptr: usize,
+    relative_ptr: i64,
+    output_buffer: VecDeque<i64>
+}
+
+#[cfg(all(test, feature = "std"))]
+mod test {
+    use super::*;
+    use std::sync::mpsc;
+
+    const DAY5_COMPLEX: [i64; 47] = [3,21,1008,21,8,20,1005,20,22,107,8,21,20,1006,20,31,1106,0,36,98,0,0,1002,21,125,20,4,20,1105,1,46,104,999,1105,1,46,1101,1000,1,20,4,20,1105,1,46,98,99];
+
+    #[test]
+    fn test_relative() {
+        {
+            let machine = IntCode::init(&[109,1,204,-1,1001,100,1,100,1008,100,16,101,1006,101,0,99],
+                                        ::std::iter::empty());
+            let output: Vec<i64> = machine.output_stream().collect();
+            assert_eq!(output, [109,1,204,-1,1001,100,1,100,1008,100,16,101,1006,101,0,99]);
+        }
+
+        {
+            let machine = IntCode::init(&[1102,34915192,34915192,7,4,7,99,0],
+                                        ::std::iter::empty());
+            let output: Vec<i64> = machine.output_stream().collect();
+            assert_eq!(output, [1219070632396864]);
+        }
+
+        {
+            let machine = IntCode::init(&[104,1125899906842624,99],
+                                        ::std::iter::empty());
+            let output: Vec<i64> = machine.output_stream().collect();
+            assert_eq!(output, [1125899906842624]);
+        }
+    }
+
+    fn disassemble(program: &[i64]) -> String {
+        decode(program, 0).unwrap().0.to_string()
+    }
+
+    #[test]
+    fn test_decode_mul() {
+        let (instruction, next) = decode(&[99,1002,4,10,2], 1).unwrap();
+        assert_eq!(instruction, Instruction::Mul {
+            left_op: ParameterType::Ref(4),
+            right_op: ParameterType::Value(10),
+            into: ParameterType::Ref(2)
+        });
+        assert_eq!(next, 5);
+        assert_eq!(instruction.to_string(), "MUL [4], #10 -> [2]");
+        assert_eq!(disassemble(&[22202,4,10,2]), "MUL r+4, r+10 -> r+2");
+    }
+
+    #[test]
+    fn test_display_opcodes() {
+        assert_eq!(disassemble(&[1,4,5,6]), "ADD [4], [5] -> [6]");
+        assert_eq!(disassemble(&[1002,4,3,4]), "MUL [4], #3 -> [4]");
+        assert_eq!(disassemble(&[3,7]), "IN -> [7]");
+        assert_eq!(disassemble(&[104,42]), "OUT #42");
+        assert_eq!(disassemble(&[1105,1,9]), "JT #1, #9");
+        assert_eq!(disassemble(&[1006,7,0]), "JF [7], #0");
+        assert_eq!(disassemble(&[7,1,2,3]), "LT [1], [2] -> [3]");
+        assert_eq!(disassemble(&[1108,8,8,0]), "EQ #8, #8 -> [0]");
+        assert_eq!(disassemble(&[109,-3]), "RB #-3");
+        assert_eq!(disassemble(&[99]), "HALT");
+    }
+
+    #[test]
+    fn test_display_parameter_modes() {
+        assert_eq!(disassemble(&[4,12]), "OUT [12]");
+        assert_eq!(disassemble(&[104,-12]), "OUT #-12");
+        assert_eq!(disassemble(&[204,5]), "OUT r+5");
+        assert_eq!(disassemble(&[204,-5]), "OUT r-5");
+        assert_eq!(disassemble(&[204,0]), "OUT r+0");
+        assert_eq!(disassemble(&[21201,1,-2,3]), "ADD r+1, #-2 -> r+3");
+        assert_eq!(disassemble(&[209,5]), "RB r+5");
+    }
+
+    #[test]
+    fn test_error_includes_instruction() {
+        let mut machine = IntCode::init(&[203,-2,99], ::std::iter::empty());
+        let error = machine.run_tick().unwrap_err();
+        assert_eq!(error.to_string(), "IN -> r-2: Ran out of input");
+    }
+
+    fn run_error(program: &[i64]) -> Option<IntCodeError> {
+        let mut machine = IntCode::init(program, ::std::iter::empty());
+        let error = machine.run_to_termination().unwrap_err();
+        IntCodeError::find(error.as_ref()).copied()
+    }
+
+    #[test]
+    fn test_structured_errors() {
+        assert_eq!(run_error(&[42]), Some(IntCodeError::InvalidOpcode(42)));
+        assert_eq!(run_error(&[-1]), Some(IntCodeError::InvalidOpcode(-1)));
+        assert_eq!(run_error(&[301,0,0,0]), Some(IntCodeError::InvalidParameterMode(301)));
+        assert_eq!(run_error(&[11101,1,1,0]), Some(IntCodeError::WriteToImmediate));
+        assert_eq!(run_error(&[3,0]), Some(IntCodeError::OutOfInput));
+        assert_eq!(run_error(&[1,-1,0,0]), Some(IntCodeError::NegativeAddress(-1)));
+        assert_eq!(run_error(&[22201,-3,0,0]), Some(IntCodeError::NegativeAddress(-3)));
+        assert_eq!(run_error(&[1,0,0]), Some(IntCodeError::AddressOutOfBounds(3)));
+        assert_eq!(run_error(&[1101,0,0,0]), Some(IntCodeError::AddressOutOfBounds(4)));
+
+        let mut machine = IntCode::init(&[1001,10,1,0,99], ::std::iter::empty());
+        machine.set_strict_reads(true);
+        let error = machine.run_to_termination().unwrap_err();
+        assert_eq!(IntCodeError::find(error.as_ref()), None);
+    }
+
+    fn uninitialized_read(error: Box<dyn ::std::error::Error>) -> UninitializedRead {
+        let error = error.downcast::<InstructionError>().unwrap();
+        let source = error.error.downcast::<UninitializedRead>().unwrap();
+        *source
+    }
+
+    #[test]
+    fn test_strict_reads_beyond_program() {
+        // 0: ADD [10], #1 -> [0]; 4: OUT [0]
+        let program = [1001,10,1,0,4,0,99];
+
+        let machine = IntCode::init(&program, ::std::iter::empty());
+        let output: Vec<i64> = machine.output_stream().collect();
+        assert_eq!(output, [1]);
+
+        let mut machine = IntCode::init(&program, ::std::iter::empty());
+        machine.set_strict_reads(true);
+        let error = machine.run_to_termination().unwrap_err();
+        assert_eq!(uninitialized_read(error), UninitializedRead { address: 10, ip: 0 });
+    }
+
+    #[test]
+    fn test_strict_reads_sparse_memory() {
+        // 0: ADD #5, #0 -> [20]; 4: ADD [20], [15] -> [0]; 8: OUT [0]
+        let program = [1101,5,0,20,1,20,15,0,4,0,99];
+
+        let machine = IntCode::init(&program, ::std::iter::empty());
+        let output: Vec<i64> = machine.output_stream().collect();
+        assert_eq!(output, [5]);
+
+        let mut machine = IntCode::init(&program, ::std::iter::empty());
+        machine.set_strict_reads(true);
+        let error = machine.run_to_termination().unwrap_err();
+        assert_eq!(uninitialized_read(error), UninitializedRead { address: 15, ip: 4 });
+
+        // reading back the written cell alone is fine
+        let mut machine = IntCode::init(&[1101,5,0,20,4,20,99], ::std::iter::empty());
+        machine.set_strict_reads(true);
+        machine.run_to_termination().unwrap();
+        assert_eq!(machine.output_buffer, [5]);
+    }
+
+    #[test]
+    fn test_cycle_detection_infinite_loop() {
+        // 0: JT #1, #0
+        let mut machine = IntCode::init(&[1105,1,0], ::std::iter::empty());
+        let error = machine.run_with_cycle_detection(1).unwrap_err();
+        assert_eq!(*error.downcast::<CycleDetected>().unwrap(), CycleDetected { period: 1 });
+
+        // 0: ADD [12], #1 -> [12]; 4: ADD [12], #-1 -> [12]; 8: JT #1, #0
+        let program = [1001,12,1,12,1001,12,-1,12,1105,1,0,99,0];
+        let mut machine = IntCode::init(&program, ::std::iter::empty());
+        let error = machine.run_with_cycle_detection(2).unwrap_err();
+        assert_eq!(*error.downcast::<CycleDetected>().unwrap(), CycleDetected { period: 6 });
+
+        assert_eq!(IntCode::init(&program, ::std::iter::empty()).state_fingerprint(),
+                   IntCode::init(&program, ::std::iter::empty()).state_fingerprint());
+    }
+
+    #[test]
+    fn test_cycle_detection_terminating() {
+        // counts [13] up to 10 before halting
+        let program = [1001,13,1,13,1007,13,10,14,1005,14,0,99,0,0,0];
+        let mut machine = IntCode::init(&program, ::std::iter::empty());
+        machine.run_with_cycle_detection(1).unwrap();
+        assert!(machine.is_terminated());
+
+        let mut machine = IntCode::init(&[109,1,204,-1,1001,100,1,100,1008,100,16,101,1006,101,0,99],
+                                        ::std::iter::empty());
+        machine.run_with_cycle_detection(3).unwrap();
+        assert_eq!(machine.output_buffer.len(), 16);
+    }
+
+    #[test]
+    fn test_run_until_output_or_input() {
+        // echoes input doubled until it reads 0
+        let program = [3,15,1006,15,14,1002,15,2,16,4,16,1105,1,0,99,0,0];
+        let mut machine = IntCode::init(&program, InputQueue::new());
+
+        assert_eq!(machine.run_until_output_or_input().unwrap(), HaltReason::NeedInput);
+        assert_eq!(machine.run_until_output_or_input().unwrap(), HaltReason::NeedInput);
+
+        machine.input_mut().push(21);
+        assert_eq!(machine.run_until_output_or_input().unwrap(), HaltReason::Output);
+        assert_eq!(machine.take_outputs(), [42]);
+        assert_eq!(machine.run_until_output_or_input().unwrap(), HaltReason::NeedInput);
+
+        machine.input_mut().push(0);
+        assert_eq!(machine.run_until_output_or_input().unwrap(), HaltReason::Terminated);
+        assert!(machine.take_outputs().is_empty());
+    }
+
+    #[test]
+    fn test_poll() {
+        let mut machine = IntCode::init(&[1,0,0,0,104,7,3,0,99], InputQueue::new());
+        assert_eq!(machine.poll().unwrap(), HaltReason::Running);
+        assert_eq!(machine.poll().unwrap(), HaltReason::Output);
+        assert_eq!(machine.take_outputs(), [7]);
+        assert_eq!(machine.poll().unwrap(), HaltReason::NeedInput);
+        assert_eq!(machine.poll().unwrap(), HaltReason::NeedInput);
+
+        machine.input_mut().push(1);
+        assert_eq!(machine.poll().unwrap(), HaltReason::Running);
+        assert_eq!(machine.poll().unwrap(), HaltReason::Terminated);
+        assert_eq!(machine.poll().unwrap(), HaltReason::Terminated);
+    }
+
+    #[test]
+    fn test_run_until_output_or_input_error() {
+        let mut machine = IntCode::init(&[1,0,0,0,42], InputQueue::new());
+        assert!(machine.run_until_output_or_input().is_err());
+    }
+
+    // Advances by one millisecond every time it is read.
+    struct TickClock(Duration);
+
+    impl Clock for TickClock {
+        fn now(&mut self) -> Duration {
+            self.0 += Duration::from_millis(1);
+            self.0
+        }
+    }
+
+    #[test]
+    fn test_run_for() {
+        let mut clock = TickClock(Duration::from_millis(0));
+
+        // 0: JT #1, #0
+        let mut machine = IntCode::init(&[1105,1,0], InputQueue::new());
+        let status = machine.run_for_with_clock(Duration::from_millis(5), &mut clock).unwrap();
+        assert_eq!(status, RunStatus { reason: None, ticks: 4 });
+
+        let program = [3,15,1006,15,14,1002,15,2,16,4,16,1105,1,0,99,0,0];
+        let mut machine = IntCode::init(&program, InputQueue::new());
+        let status = machine.run_for_with_clock(Duration::from_millis(100), &mut clock).unwrap();
+        assert_eq!(status, RunStatus { reason: Some(HaltReason::NeedInput), ticks: 0 });
+
+        machine.input_mut().push(21);
+        let status = machine.run_for_with_clock(Duration::from_millis(2), &mut clock).unwrap();
+        assert_eq!(status, RunStatus { reason: None, ticks: 1 });
+        let status = machine.run_for_with_clock(Duration::from_millis(100), &mut clock).unwrap();
+        assert_eq!(status, RunStatus { reason: Some(HaltReason::Output), ticks: 3 });
+        assert_eq!(machine.take_outputs(), [42]);
+
+        machine.input_mut().push(0);
+        let status = machine.run_for_with_clock(Duration::from_millis(100), &mut clock).unwrap();
+        assert_eq!(status, RunStatus { reason: Some(HaltReason::Terminated), ticks: 4 });
+        let status = machine.run_for(Duration::from_millis(100)).unwrap();
+        assert_eq!(status, RunStatus { reason: Some(HaltReason::Terminated), ticks: 0 });
+    }
+
+    #[test]
+    fn test_run_to_writer() {
+        let quine = [109,1,204,-1,1001,100,1,100,1008,100,16,101,1006,101,0,99];
+        let buffered: Vec<i64> = IntCode::init(&quine, ::std::iter::empty()).output_stream().collect();
+
+        let mut written = Vec::new();
+        IntCode::init(&quine, ::std::iter::empty()).run_to_writer(&mut written).unwrap();
+        let expected: String = buffered.iter().map(|value| format!("{}\n", value)).collect();
+        assert_eq!(String::from_utf8(written).unwrap(), expected);
+
+        // "Hi\n", then a value too large for a byte
+        let program = [104,72,104,105,104,10,104,1000,99];
+        let mut written = Vec::new();
+        IntCode::init(&program, ::std::iter::empty()).run_to_writer_as(&mut written, OutputFormat::Ascii).unwrap();
+        assert_eq!(written, b"Hi\n1000");
+    }
+
+    #[test]
+    fn test_memory_diff() {
+        let mut machine = IntCode::init(&[1,9,10,3,2,3,11,0,99,30,40,50], ::std::iter::empty());
+        machine.run_to_termination().unwrap();
+        assert_eq!(memory_diff(machine.memory(), &[3500,9,10,70,2,3,11,0,99,30,40,50]), []);
+        assert_eq!(memory_diff(machine.memory(), &[3500,9,10,71,2,3,11,0,99,30,40,50]), [(3, 70, 71)]);
+
+        // missing cells are zero
+        assert_eq!(memory_diff(&[1,2,0], &[1,2]), []);
+        assert_eq!(memory_diff(&[1,2], &[1,2,0,4]), [(3, 0, 4)]);
+    }
+
+    #[test]
+    fn test_init_with_patches() {
+        // 0: ADD [0], [0] -> [0] patched to MUL [4], [4] -> [0]
+        let mut machine = IntCode::init_with_patches(&[1,0,0,0,99], &[(0, 2), (1, 4), (2, 4)], ::std::iter::empty()).unwrap();
+        machine.run_to_termination().unwrap();
+        assert_eq!(machine.memory(), [9801,4,4,0,99]);
+        assert_eq!(machine.into_memory(), vec![9801,4,4,0,99]);
+
+        let error = IntCode::init_with_patches(&[1,0,0,0,99], &[(1, 4), (5, 0)], ::std::iter::empty()).err().unwrap();
+        assert_eq!(IntCodeError::find(error.as_ref()), Some(&IntCodeError::AddressOutOfBounds(5)));
+    }
+
+    #[test]
+    fn test_reset() {
+        // prints input * 2 for every input, working in [11] past the end
+        // of the program
+        // 0: IN -> [11]; 2: MUL [11], #2 -> [11]; 6: OUT [11]; 8: JT #1, #0
+        let program = [3,11,1002,11,2,11,4,11,1105,1,0];
+        let mut machine = IntCode::init(&program, vec![5, 6].into_iter());
+        assert_eq!(machine.run_to_next_output(), Some(10));
+        assert_eq!(machine.run_to_next_output(), Some(12));
+        assert_ne!(machine.memory(), program);
+
+        machine.reset();
+        assert_eq!(machine.memory(), program);
+        machine.set_input(vec![20].into_iter());
+        assert_eq!(machine.run_to_next_output(), Some(40));
+        assert!(machine.run_until_output_or_input().unwrap() == HaltReason::NeedInput);
+
+        // a patched program resets to its patched state
+        let mut machine = IntCode::init_with_patches(&[1,0,0,0,99], &[(0, 2)], ::std::iter::empty()).unwrap();
+        machine.run_to_termination().unwrap();
+        machine.reset();
+        assert_eq!(machine.memory(), [2,0,0,0,99]);
+        assert!(!machine.is_terminated());
+    }
+
+    #[test]
+    fn test_program_copied_on_write() {
+        // a machine that only reads keeps sharing the program it was given
+        let mut machine = IntCode::init(&[104,7,99], ::std::iter::empty());
+        assert!(Arc::ptr_eq(&machine.program, &machine.memory));
+        machine.run_to_termination().unwrap();
+        assert!(Arc::ptr_eq(&machine.program, &machine.memory));
+        assert_eq!(machine.into_memory(), [104,7,99]);
+
+        // the first write takes a copy, which reset then refills in place
+        let mut machine = IntCode::init(&[1,0,0,0,99], ::std::iter::empty());
+        machine.run_to_termination().unwrap();
+        assert!(!Arc::ptr_eq(&machine.program, &machine.memory));
+        assert_eq!(*machine.program, [1,0,0,0,99]);
+        let buffer = machine.memory().as_ptr();
+        machine.reset();
+        assert_eq!(machine.memory(), [1,0,0,0,99]);
+        assert_eq!(machine.memory().as_ptr(), buffer);
+    }
+
+    #[test]
+    fn test_reload() {
+        let mut machine = IntCode::init(&[1,0,0,0,104,5,99], ::std::iter::empty());
+        machine.run_to_termination().unwrap();
+        assert_eq!(machine.memory()[0], 2);
+
+        machine.reload(&[1,0,0,0,99]);
+        assert!(!machine.is_terminated());
+        assert!(machine.take_outputs().is_empty());
+        machine.memory_mut()[1] = 4;
+        machine.run_to_termination().unwrap();
+        assert_eq!(machine.memory(), [100,4,0,0,99]);
+
+        // resetting goes back to the reloaded program, without the patch
+        machine.reset();
+        assert_eq!(machine.memory(), [1,0,0,0,99]);
+    }
+
+    #[test]
+    fn test_trace() {
+        let mut machine = IntCode::init(&[1,0,0,0,99], ::std::iter::empty());
+        machine.enable_trace(true);
+        machine.run_to_termination().unwrap();
+        assert_eq!(machine.trace(), ["ADD [0], [0] -> [0] | 1, 1 | [0] <- 2", "HALT"]);
+
+        let mut machine = IntCode::init(&[3,0,4,0,99], ::std::iter::once(7));
+        machine.run_to_termination().unwrap();
+        assert!(machine.trace().is_empty());
+    }
+
+    #[test]
+    fn test_hot_loop_busy_wait() {
+        // increments address 8 forever: 0: ADD [8], #1 -> [8]; 4: JNZ #1, #0
+        let (tx, rx) = mpsc::channel();
+        let mut machine = IntCode::init(&[1001,8,1,8,1105,1,0,99,0], ::std::iter::empty());
+        machine.detect_hot_loops(100, move |hot_loop| tx.send(hot_loop.clone()).unwrap());
+
+        for _ in 0..1000 {
+            machine.run_tick().unwrap();
+        }
+
+        let reports: Vec<HotLoop> = rx.try_iter().collect();
+        assert_eq!(reports.len(), 1);
+        assert_eq!(reports[0].addresses, vec![0, 4]);
+        assert!(reports[0].ticks > 100);
+    }
+
+    #[test]
+    fn test_hot_loop_day5_sample() {
+        for input in &[7, 8, 9] {
+            let (tx, rx) = mpsc::channel();
+            let mut machine = IntCode::init(&DAY5_COMPLEX, ::std::iter::once(*input));
+            machine.detect_hot_loops(2, move |hot_loop| tx.send(hot_loop.clone()).unwrap());
+            machine.run_to_termination().unwrap();
+
+            assert_eq!(rx.try_iter().count(), 0);
+        }
+    }
+
+    #[test]
+    fn test_hot_loop_varied_addresses() {
+        // a long straight-line computation never revisits an address
+        let mut program = Vec::new();
+        for _ in 0..500 {
+            program.extend_from_slice(&[1001, 0, 1, 0]);
+        }
+        program.push(99);
+
+        let (tx, rx) = mpsc::channel();
+        let mut machine = IntCode::init(&program, ::std::iter::empty());
+        machine.detect_hot_loops(50, move |hot_loop| tx.send(hot_loop.clone()).unwrap());
+        machine.run_to_termination().unwrap();
+
+        assert_eq!(rx.try_iter().count(), 0);
+    }
+
+    #[test]
+    fn test_on_output() {
+        let (tx, rx) = mpsc::channel();
+        let mut machine = IntCode::init(&[109,1,204,-1,1001,100,1,100,1008,100,16,101,1006,101,0,99],
+                                        ::std::iter::empty());
+        machine.on_output(move |value| tx.send(value).unwrap());
+
+        // observed as they are produced, before the stream is read
+        machine.run_to_next_output();
+        assert_eq!(rx.try_iter().collect::<Vec<_>>(), [109]);
+
+        let outputs: Vec<i64> = machine.output_stream().collect();
+        assert_eq!(outputs.len(), 15);
+        assert_eq!(rx.try_iter().collect::<Vec<_>>(), outputs);
+    }
+
+    #[test]
+    fn test_hot_loop_blocked_input() {
+        // polling while the program waits for input is not a loop
+        let (tx, rx) = mpsc::channel();
+        let mut machine = IntCode::init(&[3,0,99], ::std::iter::empty());
+        machine.detect_hot_loops(5, move |hot_loop| tx.send(hot_loop.clone()).unwrap());
+
+        for _ in 0..50 {
+            assert_eq!(machine.poll().unwrap(), HaltReason::NeedInput);
+        }
+
+        assert_eq!(rx.try_iter().count(), 0);
+    }
+
+    #[test]
+    fn test_hot_loop_reset_by_output() {
+        // day 9 quine loops over the same addresses but outputs every iteration
+        let (tx, rx) = mpsc::channel();
+        let mut machine = IntCode::init(&[109,1,204,-1,1001,100,1,100,1008,100,16,101,1006,101,0,99],
+                                        ::std::iter::empty());
+        machine.detect_hot_loops(5, move |hot_loop| tx.send(hot_loop.clone()).unwrap());
+        machine.run_to_termination().unwrap();
+
+        assert_eq!(rx.try_iter().count(), 0);
+    }
+}
@@ -0,0 +1,81 @@
+//!
+//! JavaScript bindings, enabled with the `wasm-bindgen` feature. The
+//! machine is driven from the page: feed it input, run it until it
+//! pauses, then collect whatever it printed.
+//!
+
+use wasm_bindgen::prelude::*;
+
+use crate::{HaltReason, InputQueue, IntCode};
+
+#[wasm_bindgen]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum State {
+    Ready = "ready",
+    Output = "output",
+    NeedInput = "need_input",
+    Terminated = "terminated"
+}
+
+#[wasm_bindgen]
+pub struct Machine {
+    machine: IntCode<InputQueue>,
+    state: State
+}
+
+#[wasm_bindgen]
+impl Machine {
+    #[wasm_bindgen(constructor)]
+    pub fn new(program: &[i64]) -> Machine {
+        Machine {
+            machine: IntCode::init(program, InputQueue::new()),
+            state: State::Ready
+        }
+    }
+
+    pub fn push_input(&mut self, value: i64) {
+        self.machine.input_mut().push(value);
+    }
+
+    pub fn run_until_output_or_input(&mut self) -> Result<State, JsError> {
+        let reason = self.machine.run_until_output_or_input()
+            .map_err(|e| JsError::new(&e.to_string()))?;
+
+        self.state = match reason {
+            HaltReason::Running => State::Ready,
+            HaltReason::Output => State::Output,
+            HaltReason::NeedInput => State::NeedInput,
+            HaltReason::Terminated => State::Terminated
+        };
+        Ok(self.state)
+    }
+
+    pub fn take_outputs(&mut self) -> Vec<i64> {
+        self.machine.take_outputs()
+    }
+
+    pub fn state(&self) -> State {
+        self.state
+    }
+}
+
+#[cfg(all(test, target_arch = "wasm32"))]
+mod test {
+    use super::*;
+    use wasm_bindgen_test::*;
+
+    #[wasm_bindgen_test]
+    fn test_echo_doubled() {
+        let mut machine = Machine::new(&[3,15,1006,15,14,1002,15,2,16,4,16,1105,1,0,99,0,0]);
+        assert_eq!(machine.state(), State::Ready);
+        assert_eq!(machine.run_until_output_or_input().unwrap(), State::NeedInput);
+
+        machine.push_input(21);
+        assert_eq!(machine.run_until_output_or_input().unwrap(), State::Output);
+        assert_eq!(machine.take_outputs(), [42]);
+
+        machine.push_input(0);
+        assert_eq!(machine.run_until_output_or_input().unwrap(), State::Terminated);
+        assert_eq!(machine.state(), State::Terminated);
+    }
+}
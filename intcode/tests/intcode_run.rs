@@ -0,0 +1,61 @@
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Output, Stdio};
+
+//
+// Prints "> ", then echoes a line back; stops at an empty line.
+//
+const ECHO: &str = "104,62,104,32,3,100,1008,100,10,101,1005,101,29,4,100,3,100,4,100,1008,100,10,101,1006,101,15,1105,1,0,99";
+
+// Reads two numbers and prints their sum and product.
+const SUM_PRODUCT: &str = "3,20,3,21,1,20,21,22,4,22,2,20,21,22,4,22,99";
+
+fn run(name: &str, program: &str, args: &[&str], stdin: &str) -> Output {
+    let path: PathBuf = [env!("CARGO_TARGET_TMPDIR"), name].iter().collect();
+    fs::write(&path, program).unwrap();
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_intcode-run"))
+        .args(args)
+        .arg(&path)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    child.stdin.take().unwrap().write_all(stdin.as_bytes()).unwrap();
+    child.wait_with_output().unwrap()
+}
+
+#[test]
+fn test_ascii_dialog() {
+    let output = run("echo.int", ECHO, &["--ascii"], "hello\nIntCode 2019\n\nnever read\n");
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "> hello\n> IntCode 2019\n> ");
+}
+
+#[test]
+fn test_decimal_dialog() {
+    // inputs may share a line or be split over several
+    for stdin in ["6 7\n", "6\n\n7\n"].iter() {
+        let output = run("sum_product.int", SUM_PRODUCT, &[], stdin);
+        assert!(output.status.success());
+        assert_eq!(String::from_utf8(output.stdout).unwrap(), "13\n42\n");
+    }
+}
+
+#[test]
+fn test_errors() {
+    let output = run("closed.int", SUM_PRODUCT, &[], "6\n");
+    assert!(!output.status.success());
+    assert!(String::from_utf8(output.stderr).unwrap().contains("stdin is closed"));
+
+    let output = run("bad_input.int", SUM_PRODUCT, &[], "six\n");
+    assert!(!output.status.success());
+    assert!(String::from_utf8(output.stderr).unwrap().contains("invalid input"));
+
+    let output = run("bad_program.int", "1,2,x", &[], "");
+    assert!(!output.status.success());
+    assert!(String::from_utf8(output.stderr).unwrap().contains("invalid value"));
+}